@@ -0,0 +1,227 @@
+use crate::Reader;
+use std::io::{self, prelude::*};
+
+/// A buffering [`Reader`] adaptor, implementing [`BufRead`] so that data can be read a line or a
+/// delimiter at a time. Created by [`Reader::buffered`].
+///
+/// Unlike wrapping a `Reader` in [`std::io::BufReader`], the non-blocking semantics are
+/// preserved: when no data is buffered and none is available from the pipe,
+/// [`fill_buf`](BufRead::fill_buf) returns an error of kind
+/// [`WouldBlock`](io::ErrorKind::WouldBlock) rather than an empty buffer, which would be
+/// mistaken for EOF. Note that [`BufRead::read_until`] and [`BufRead::read_line`] append what
+/// they have read so far before returning such an error, so they can simply be called again with
+/// the same buffer once the pipe is readable.
+#[derive(Debug)]
+pub struct BufferedReader {
+    reader: Reader,
+    buf: Vec<u8>,
+    cap: usize,
+    // The position of the first byte in `buf` that has not been consumed.
+    pos: usize,
+}
+
+impl BufferedReader {
+    const DEFAULT_CAPACITY: usize = 8192;
+
+    pub(crate) fn new(reader: Reader) -> BufferedReader {
+        BufferedReader::with_capacity(reader, BufferedReader::DEFAULT_CAPACITY)
+    }
+
+    /// Creates a `BufferedReader` whose internal buffer holds at most `cap` bytes. A small
+    /// buffer hands data on as soon as it arrives in small pieces, while a large one means fewer
+    /// reads from the pipe; [`Reader::buffered`] uses 8KiB. The buffer also bounds the length of
+    /// a token read with [`fill_until`](BufferedReader::fill_until).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cap` is zero.
+    #[must_use]
+    pub fn with_capacity(reader: Reader, cap: usize) -> BufferedReader {
+        assert!(cap > 0, "buffer capacity must be non-zero");
+        BufferedReader {
+            reader,
+            buf: Vec::with_capacity(cap),
+            cap,
+            pos: 0,
+        }
+    }
+
+    /// The buffered data that has not been consumed yet. Unlike
+    /// [`fill_buf`](BufRead::fill_buf), this never reads from the pipe.
+    #[inline]
+    #[must_use]
+    pub fn buffer(&self) -> &[u8] {
+        &self.buf[self.pos..]
+    }
+
+    /// The maximum number of bytes the internal buffer can hold.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    /// Reads until the byte `delim` is buffered, then returns the buffered data up to and
+    /// including it without consuming it; call [`consume`](BufRead::consume) with the length of
+    /// the returned slice once done with it. Unlike [`BufRead::read_until`], the token is
+    /// gathered in the internal buffer, so no more than [`capacity`](BufferedReader::capacity)
+    /// bytes are ever held for it.
+    ///
+    /// If the writer has closed the pipe, whatever remains is returned without a delimiter, and
+    /// an empty slice once nothing does. Returns an error of kind
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock) if the delimiter has not arrived yet, in which
+    /// case the data read so far stays buffered, and an error of kind
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the buffer fills up before it does.
+    pub fn fill_until(&mut self, delim: u8) -> io::Result<&[u8]> {
+        let mut searched = 0;
+        loop {
+            let found = self.buf[self.pos + searched..]
+                .iter()
+                .position(|&b| b == delim);
+            if let Some(i) = found {
+                return Ok(&self.buf[self.pos..=self.pos + searched + i]);
+            }
+            searched = self.buf.len() - self.pos;
+            if searched >= self.cap {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "delimiter not found within the buffer capacity",
+                ));
+            }
+            // Move the unconsumed data to the front to make room for the rest of the token.
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+            self.buf.reserve_exact(self.cap - self.buf.len());
+            match self.reader.read_into_spare(&mut self.buf) {
+                Ok(0) => return Ok(&self.buf),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns the underlying [`Reader`]. Any buffered data is lost.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+}
+
+impl Read for BufferedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Bypass the internal buffer for large reads when it is empty.
+        if self.pos == self.buf.len() && buf.len() >= self.cap {
+            return self.reader.read(buf);
+        }
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for BufferedReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+            self.buf.reserve_exact(self.cap);
+            self.reader.read_into_spare(&mut self.buf)?;
+        }
+        Ok(&self.buf[self.pos..])
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.buf.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_until_split() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.buffered();
+        let mut line = Vec::new();
+        assert_eq!(
+            reader.read_until(b'\n', &mut line).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        writer.write_all(b"Hello, ").unwrap();
+        assert_eq!(
+            reader.read_until(b'\n', &mut line).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        writer.write_all(b"world\nmore").unwrap();
+        assert_eq!(reader.read_until(b'\n', &mut line).unwrap(), 6);
+        assert_eq!(line, b"Hello, world\n");
+        let mut rest = String::new();
+        drop(writer);
+        assert_eq!(reader.read_line(&mut rest).unwrap(), 4);
+        assert_eq!(rest, "more");
+        assert_eq!(reader.read_line(&mut rest).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.buffered();
+        writer.write_all(b"Hello, world").unwrap();
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        assert_eq!(reader.fill_buf().unwrap(), b", world");
+        let mut large = vec![0; 16 * 1024];
+        assert_eq!(reader.read(&mut large).unwrap(), 7);
+        assert_eq!(
+            reader.read(&mut large).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+    }
+
+    #[test]
+    fn test_tiny_capacity() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = BufferedReader::with_capacity(reader, 4);
+        assert_eq!(reader.capacity(), 4);
+        writer.write_all(b"Hello\n").unwrap();
+        assert_eq!(reader.fill_buf().unwrap(), b"Hell");
+        assert_eq!(reader.buffer(), b"Hell");
+        assert_eq!(
+            reader.fill_until(b'\n').unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        reader.consume(4);
+        assert!(reader.buffer().is_empty());
+        assert_eq!(reader.fill_until(b'\n').unwrap(), b"o\n");
+    }
+
+    #[test]
+    fn test_large_capacity() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = BufferedReader::with_capacity(reader, 64 * 1024);
+        let line: Vec<u8> = (b'a'..=b'z').cycle().take(32 * 1024).collect();
+        writer.write_all(&line[..16 * 1024]).unwrap();
+        assert_eq!(
+            reader.fill_until(b'\n').unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        assert_eq!(reader.buffer().len(), 16 * 1024);
+        writer.write_all(&line[16 * 1024..]).unwrap();
+        writer.write_all(b"\nrest").unwrap();
+        let token = reader.fill_until(b'\n').unwrap();
+        assert_eq!(&token[..token.len() - 1], &line[..]);
+        let len = token.len();
+        reader.consume(len);
+        drop(writer);
+        assert_eq!(reader.fill_until(b'\n').unwrap(), b"rest");
+        reader.consume(4);
+        assert!(reader.fill_until(b'\n').unwrap().is_empty());
+    }
+}
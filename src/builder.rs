@@ -0,0 +1,227 @@
+use crate::{Event, Poll, Reader, Token, Writer};
+use libc::c_int;
+use std::io;
+
+/// A builder for configuring how a pipe is created. [`PipeBuilder::new`] yields the same
+/// configuration as [`pipelib::new`](crate::new): both ends are non-blocking, and have the
+/// [`FD_CLOEXEC`](libc::FD_CLOEXEC) flag set.
+#[derive(Debug, Clone)]
+pub struct PipeBuilder {
+    nonblocking: bool,
+    cloexec: bool,
+    packet_mode: bool,
+    capacity: Option<usize>,
+}
+
+impl Default for PipeBuilder {
+    fn default() -> Self {
+        PipeBuilder {
+            nonblocking: true,
+            cloexec: true,
+            packet_mode: false,
+            capacity: None,
+        }
+    }
+}
+
+impl PipeBuilder {
+    #[inline]
+    #[must_use]
+    pub fn new() -> PipeBuilder {
+        Default::default()
+    }
+
+    /// Sets whether both ends are put into non-blocking mode. Enabled by default.
+    #[inline]
+    #[must_use]
+    pub fn nonblocking(mut self, nonblocking: bool) -> PipeBuilder {
+        self.nonblocking = nonblocking;
+        self
+    }
+
+    /// Sets whether the [`FD_CLOEXEC`](libc::FD_CLOEXEC) flag is set on both ends. Enabled by
+    /// default; disabling it lets child processes inherit the pipe.
+    #[inline]
+    #[must_use]
+    pub fn cloexec(mut self, cloexec: bool) -> PipeBuilder {
+        self.cloexec = cloexec;
+        self
+    }
+
+    /// Sets whether the pipe is created in packet mode, with [`O_DIRECT`](libc::O_DIRECT). In
+    /// packet mode, each write of at most [`PIPE_BUF`](libc::PIPE_BUF) bytes forms a separate
+    /// packet, and each read returns at most one packet, so message boundaries are preserved.
+    /// Only supported on Linux and Android; elsewhere, [`build`](PipeBuilder::build) fails with
+    /// an error of kind [`Unsupported`](io::ErrorKind::Unsupported). Disabled by default.
+    #[inline]
+    #[must_use]
+    pub fn packet_mode(mut self, packet_mode: bool) -> PipeBuilder {
+        self.packet_mode = packet_mode;
+        self
+    }
+
+    /// Sets the capacity of the pipe's kernel buffer, as with
+    /// [`Writer::set_pipe_size`](crate::Writer). Only supported on Linux; elsewhere,
+    /// [`build`](PipeBuilder::build) fails with an error of kind
+    /// [`Unsupported`](io::ErrorKind::Unsupported). By default, the kernel's default capacity is
+    /// used.
+    #[inline]
+    #[must_use]
+    pub fn capacity(mut self, bytes: usize) -> PipeBuilder {
+        self.capacity = Some(bytes);
+        self
+    }
+
+    /// Creates a pipe with the builder's configuration.
+    pub fn build(self) -> io::Result<(Reader, Writer)> {
+        let packet_mode_supported = cfg!(any(target_os = "linux", target_os = "android"));
+        if (self.packet_mode && !packet_mode_supported)
+            || (self.capacity.is_some() && !cfg!(target_os = "linux"))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "option is not supported on this platform",
+            ));
+        }
+        let (reader, writer) = self.create()?;
+        #[cfg(target_os = "linux")]
+        if let Some(bytes) = self.capacity {
+            writer.set_pipe_size(bytes)?;
+        }
+        Ok((reader, writer))
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    fn create(&self) -> io::Result<(Reader, Writer)> {
+        let mut flags = 0;
+        if self.nonblocking {
+            flags |= libc::O_NONBLOCK;
+        }
+        if self.cloexec {
+            flags |= libc::O_CLOEXEC;
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if self.packet_mode {
+            flags |= libc::O_DIRECT;
+        }
+        let mut fds: [c_int; 2] = [-1, -1];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), flags) } != 0 {
+            return Err(oserr!());
+        }
+        debug_assert_ne!(fds[0], -1);
+        debug_assert_ne!(fds[1], -1);
+        Ok((Reader::new(fds[0]), Writer::new(fds[1])))
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    fn create(&self) -> io::Result<(Reader, Writer)> {
+        let mut fds: [c_int; 2] = [-1, -1];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(oserr!());
+        }
+        debug_assert_ne!(fds[0], -1);
+        debug_assert_ne!(fds[1], -1);
+        // Wrap the fds immediately so that they're closed if setting their flags fails.
+        let (reader, writer) = (Reader::new(fds[0]), Writer::new(fds[1]));
+        unsafe {
+            for fd in fds {
+                if self.cloexec && libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
+                    return Err(oserr!());
+                }
+            }
+        }
+        if self.nonblocking {
+            reader.set_nonblocking(true)?;
+            writer.set_nonblocking(true)?;
+        }
+        Ok((reader, writer))
+    }
+
+    /// Creates a pipe, [registers](Poll::register_owned) its read end with `poll` under
+    /// `read_token`, and returns the write end. The read end is owned by `poll` and closed along
+    /// with it.
+    pub fn register_into(
+        self,
+        poll: &mut Poll,
+        read_token: Token,
+        read_events: Event,
+    ) -> io::Result<Writer> {
+        let (reader, writer) = self.build()?;
+        poll.register_owned(reader, read_token, read_events);
+        Ok(writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Timeout;
+    use std::{io::prelude::*, os::unix::prelude::AsRawFd};
+
+    #[test]
+    fn test_blocking() {
+        let (reader, writer) = PipeBuilder::new()
+            .nonblocking(false)
+            .cloexec(false)
+            .build()
+            .unwrap();
+        assert!(!reader.is_nonblocking().unwrap());
+        assert!(!writer.is_nonblocking().unwrap());
+        for fd in [reader.as_raw_fd(), writer.as_raw_fd()] {
+            assert_eq!(
+                unsafe { libc::fcntl(fd, libc::F_GETFD) } & libc::FD_CLOEXEC,
+                0
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_packet_mode() {
+        let (mut reader, mut writer) = PipeBuilder::new().packet_mode(true).build().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        writer.write_all(b"world").unwrap();
+        let mut buf = [0; 64];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"Hello");
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"world");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_capacity() {
+        const SIZE: usize = 256 * 1024;
+        let (reader, writer) = PipeBuilder::new().capacity(SIZE).build().unwrap();
+        assert!(writer.pipe_size().unwrap() >= SIZE);
+        assert!(reader.describe().unwrap().nonblocking);
+    }
+
+    #[test]
+    fn test_register_into() {
+        let mut poll = Poll::new();
+        let mut writer = PipeBuilder::new()
+            .register_into(&mut poll, Token(7), Event::all_readable())
+            .unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 0);
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        let (tok, ev) = poll.events().next().unwrap();
+        assert_eq!(tok, Token(7));
+        assert!(ev.is_readable());
+    }
+}
@@ -0,0 +1,91 @@
+use crate::Writer;
+use std::io::{self, prelude::*};
+
+/// A [`Writer`] adaptor that accumulates many small writes in memory and sends them to the pipe
+/// with a single `write` call. Created by [`Writer::coalesce`].
+#[derive(Debug)]
+pub struct CoalescingWriter {
+    writer: Writer,
+    queue: Vec<u8>,
+}
+
+impl CoalescingWriter {
+    pub(crate) fn new(writer: Writer) -> CoalescingWriter {
+        CoalescingWriter {
+            writer,
+            queue: Vec::new(),
+        }
+    }
+
+    /// Appends `buf` to the internal queue without writing anything to the pipe.
+    #[inline]
+    pub fn queue(&mut self, buf: &[u8]) {
+        self.queue.extend_from_slice(buf);
+    }
+
+    /// Writes the queued bytes to the pipe with a single `write` call, returning the number of
+    /// bytes written. If the pipe cannot hold all of the queued bytes, the remainder stays queued
+    /// for the next call.
+    pub fn commit(&mut self) -> io::Result<usize> {
+        if self.queue.is_empty() {
+            return Ok(0);
+        }
+        let written = self.writer.write(&self.queue)?;
+        self.queue.drain(..written);
+        Ok(written)
+    }
+
+    /// The number of bytes queued but not yet written.
+    #[inline]
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the underlying [`Writer`], discarding any bytes that have not been committed.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Writer {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit() {
+        let (mut reader, writer) = crate::new().unwrap();
+        let mut writer = writer.coalesce();
+        let mut expected = Vec::new();
+        for i in 0..1000u16 {
+            let record = i.to_be_bytes();
+            writer.queue(&record);
+            expected.extend_from_slice(&record);
+        }
+        assert_eq!(writer.commit().unwrap(), expected.len());
+        assert_eq!(writer.queued(), 0);
+        let mut buf = vec![0; expected.len()];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_commit_short_write() {
+        let (mut reader, writer) = crate::new().unwrap();
+        let mut writer = writer.coalesce();
+        let msg: Vec<u8> = (0..=250).cycle().take(1024 * 1024).collect();
+        writer.queue(&msg);
+        let first = writer.commit().unwrap();
+        assert!(first < msg.len());
+        assert_eq!(writer.queued(), msg.len() - first);
+        let mut buf = vec![0; first];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, msg[..first]);
+        let second = writer.commit().unwrap();
+        let mut buf = vec![0; second];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(buf, msg[first..first + second]);
+    }
+}
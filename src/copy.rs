@@ -0,0 +1,133 @@
+use crate::{poll::poll_fd, Event, Reader, Writer};
+use std::{
+    io::{self, prelude::*},
+    os::unix::prelude::AsRawFd,
+};
+
+/// Copies everything from `src` to `dst` until `src` reaches EOF, returning the number of bytes
+/// copied. Unlike [`std::io::copy`], waits for `src` to become readable or `dst` writable
+/// instead of failing with [`WouldBlock`](io::ErrorKind::WouldBlock).
+///
+/// On Linux, the data is moved with [`splice`](crate::splice), so it is never copied through
+/// user space; this falls back to copying through a buffer if `splice` is not supported for the
+/// file descriptors involved. Data already buffered by `src`, e.g. by [`Reader::read_until`], is
+/// written first.
+///
+/// The bytes copied count towards [`Reader::bytes_read`] and [`Writer::bytes_written`] on every
+/// platform. Returns an error of kind [`NotConnected`](io::ErrorKind::NotConnected) without
+/// reading anything if `dst` has been [sealed](Writer::seal).
+pub fn copy(src: &mut Reader, dst: &mut Writer) -> io::Result<u64> {
+    dst.check_sealed()?;
+    let pending = src.take_pending();
+    dst.write_all_blocking(&pending)?;
+    #[cfg(target_os = "linux")]
+    let copied = match splice_all(src, dst)? {
+        (copied, true) => return Ok(pending.len() as u64 + copied),
+        (copied, false) => copied,
+    };
+    #[cfg(not(target_os = "linux"))]
+    let copied = 0;
+    Ok(pending.len() as u64 + copied + buffered_copy(src, dst)?)
+}
+
+// Splices from `src` to `dst` until EOF. Returns the number of bytes moved, and whether EOF was
+// reached; `false` means that `splice` is not supported for these file descriptors.
+#[cfg(target_os = "linux")]
+fn splice_all(src: &mut Reader, dst: &mut Writer) -> io::Result<(u64, bool)> {
+    const CHUNK_SIZE: usize = 64 * 1024;
+    let mut copied = 0;
+    loop {
+        match crate::splice(src, dst, CHUNK_SIZE) {
+            Ok(0) => return Ok((copied, true)),
+            Ok(n) => copied += n as u64,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            // Either side may be the one that is not ready, so wait for both.
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                poll_fd(src.as_raw_fd(), Event::POLLIN, -1)?;
+                poll_fd(dst.as_raw_fd(), Event::POLLOUT, -1)?;
+            }
+            Err(e) if e.raw_os_error() == Some(libc::EINVAL) => return Ok((copied, false)),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn buffered_copy(src: &mut Reader, dst: &mut Writer) -> io::Result<u64> {
+    let mut buf = vec![0; 64 * 1024];
+    let mut copied = 0;
+    loop {
+        match src.read(&mut buf) {
+            Ok(0) => return Ok(copied),
+            Ok(n) => {
+                dst.write_all_blocking(&buf[..n])?;
+                copied += n as u64;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                poll_fd(src.as_raw_fd(), Event::POLLIN, -1)?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_copy() {
+        const LEN: usize = 1024 * 1024;
+        let (mut src_reader, mut src_writer) = crate::new().unwrap();
+        let (mut dst_reader, mut dst_writer) = crate::new().unwrap();
+        let msg: Vec<u8> = (0..=250).cycle().take(LEN).collect();
+        let producer = {
+            let msg = msg.clone();
+            thread::spawn(move || src_writer.write_all_blocking(&msg).unwrap())
+        };
+        let consumer = thread::spawn(move || {
+            let mut received = Vec::new();
+            dst_reader.set_nonblocking(false).unwrap();
+            dst_reader.read_to_end(&mut received).unwrap();
+            received
+        });
+        assert_eq!(copy(&mut src_reader, &mut dst_writer).unwrap(), LEN as u64);
+        assert_eq!(src_reader.bytes_read(), LEN as u64);
+        assert_eq!(dst_writer.bytes_written(), LEN as u64);
+        drop(dst_writer);
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), msg);
+    }
+
+    #[test]
+    fn test_buffered_copy() {
+        let (mut src_reader, mut src_writer) = crate::new().unwrap();
+        let (mut dst_reader, mut dst_writer) = crate::new().unwrap();
+        src_writer.write_all(b"line\nrest").unwrap();
+        drop(src_writer);
+        let mut line = Vec::new();
+        assert_eq!(src_reader.read_until(b'\n', &mut line).unwrap(), Some(5));
+        assert_eq!(buffered_copy(&mut src_reader, &mut dst_writer).unwrap(), 4);
+        drop(dst_writer);
+        let mut received = Vec::new();
+        dst_reader.read_to_end(&mut received).unwrap();
+        assert_eq!(received, b"rest");
+    }
+
+    #[test]
+    fn test_copy_sealed() {
+        let (mut src_reader, mut src_writer) = crate::new().unwrap();
+        let (_dst_reader, mut dst_writer) = crate::new().unwrap();
+        src_writer.write_all(b"Hello").unwrap();
+        drop(src_writer);
+        dst_writer.seal();
+        let err = copy(&mut src_reader, &mut dst_writer).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        assert_eq!(src_reader.bytes_read(), 0);
+        assert_eq!(dst_writer.bytes_written(), 0);
+        let mut buf = [0; 5];
+        src_reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+    }
+}
@@ -0,0 +1,170 @@
+use crate::{Event, Poll, Pollable, Timeout, Token};
+use std::{fmt, io};
+
+/// Returned by a [`Dispatcher`] handler to decide whether it stays registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    /// Keep the registration.
+    Continue,
+    /// Remove the registration; the handler is dropped and not called again.
+    Remove,
+}
+
+struct Entry {
+    token: Token,
+    handler: Box<dyn FnMut(Event) -> Control>,
+}
+
+/// A simple event loop on top of [`Poll`], which calls a handler for each registered object
+/// whenever it becomes ready, instead of leaving the caller to match on tokens.
+///
+/// As with [`Poll::register`], the caller is responsible for keeping registered objects open;
+/// typically, a handler owns a duplicate of the object it reads from or writes to.
+#[derive(Default)]
+pub struct Dispatcher {
+    poll: Poll,
+    entries: Vec<Entry>,
+}
+
+impl Dispatcher {
+    #[inline]
+    #[must_use]
+    pub fn new() -> Dispatcher {
+        Default::default()
+    }
+
+    /// Registers a [Pollable] object along with the handler to call when it receives events,
+    /// returning the token allocated for it. See [`Poll::register_auto`].
+    pub fn register<T, F>(&mut self, fd: &T, events: Event, handler: F) -> Token
+    where
+        T: Pollable,
+        F: FnMut(Event) -> Control + 'static,
+    {
+        let token = self.poll.register_auto(fd, events);
+        self.entries.push(Entry {
+            token,
+            handler: Box::new(handler),
+        });
+        token
+    }
+
+    /// Removes the registration allocated `token` and drops its handler, returning whether it
+    /// was registered.
+    pub fn deregister(&mut self, token: Token) -> bool {
+        match self.entries.iter().position(|entry| entry.token == token) {
+            Some(i) => {
+                self.entries.remove(i);
+                self.poll.deregister_token(token)
+            }
+            None => false,
+        }
+    }
+
+    /// The number of registered handlers.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether no handlers are registered.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Polls the registered objects once, then calls the handler of each object that received
+    /// events, in registration order. Each handler is called at most once per poll, with all the
+    /// flags its object received combined. Handlers returning [`Control::Remove`] are
+    /// deregistered. Returns the number of handlers called.
+    pub fn run_once(&mut self, timeout: Timeout) -> io::Result<usize> {
+        if self.poll.poll(timeout)? == 0 {
+            return Ok(0);
+        }
+        let mut ready: Vec<(Token, Event)> = Vec::new();
+        for (token, ev) in self.poll.events() {
+            match ready.last_mut() {
+                Some((last, events)) if *last == token => *events |= ev,
+                _ => ready.push((token, ev)),
+            }
+        }
+        for (token, events) in &ready {
+            if let Some(entry) = self.entries.iter_mut().find(|entry| entry.token == *token) {
+                if (entry.handler)(*events) == Control::Remove {
+                    self.deregister(*token);
+                }
+            }
+        }
+        Ok(ready.len())
+    }
+}
+
+impl fmt::Debug for Dispatcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dispatcher")
+            .field("poll", &self.poll)
+            .field(
+                "tokens",
+                &self.entries.iter().map(|e| e.token).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, io::prelude::*, rc::Rc};
+
+    #[test]
+    fn test_dispatch() {
+        let mut dispatcher = Dispatcher::new();
+        let (r1, mut w1) = crate::new().unwrap();
+        let (r2, mut w2) = crate::new().unwrap();
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        for (name, reader) in [("first", &r1), ("second", &r2)] {
+            let fired = Rc::clone(&fired);
+            let mut clone = reader.try_clone().unwrap();
+            dispatcher.register(reader, Event::READ, move |ev| {
+                fired.borrow_mut().push(name);
+                if ev.is_hangup() {
+                    return Control::Remove;
+                }
+                let mut buf = [0; 16];
+                let _ = clone.read(&mut buf);
+                Control::Continue
+            });
+        }
+        assert_eq!(dispatcher.run_once(Timeout::instant()).unwrap(), 0);
+        w2.write_all(b"Hello").unwrap();
+        assert_eq!(dispatcher.run_once(Timeout::instant()).unwrap(), 1);
+        assert_eq!(*fired.borrow(), ["second"]);
+        w1.write_all(b"world").unwrap();
+        assert_eq!(dispatcher.run_once(Timeout::instant()).unwrap(), 1);
+        assert_eq!(*fired.borrow(), ["second", "first"]);
+        drop((w1, w2));
+        assert_eq!(dispatcher.run_once(Timeout::instant()).unwrap(), 2);
+        assert!(dispatcher.is_empty());
+    }
+
+    #[test]
+    fn test_deregister_shared_fd() {
+        let mut dispatcher = Dispatcher::new();
+        let (stream, mut peer) = crate::socketpair().unwrap();
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let mut tokens = Vec::new();
+        for (name, events) in [("read", Event::READ), ("write", Event::WRITE)] {
+            let fired = Rc::clone(&fired);
+            tokens.push(dispatcher.register(&stream, events, move |_| {
+                fired.borrow_mut().push(name);
+                Control::Continue
+            }));
+        }
+        assert!(dispatcher.deregister(tokens[1]));
+        assert_eq!(dispatcher.len(), 1);
+        peer.write_all(b"Hello").unwrap();
+        assert_eq!(dispatcher.run_once(Timeout::instant()).unwrap(), 1);
+        assert_eq!(*fired.borrow(), ["read"]);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::{Event, Poll, Reader, Timeout, Token, Writer};
+use std::io::{self, prelude::*};
+
+const CHUNK_SIZE: usize = 8192;
+
+// One direction of a tunnel: bytes read from `read` are buffered until `write` accepts them.
+struct Half<'a> {
+    read: &'a mut Reader,
+    write: &'a mut Writer,
+    buf: Box<[u8]>,
+    pos: usize,
+    filled: usize,
+}
+
+impl<'a> Half<'a> {
+    fn new(read: &'a mut Reader, write: &'a mut Writer) -> Half<'a> {
+        Half {
+            read,
+            write,
+            buf: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    // Waits for the writer while there is buffered data, and for the reader otherwise.
+    fn register(&self, poll: &mut Poll, token: Token) {
+        if self.pos < self.filled {
+            poll.register(&*self.write, token, Event::WRITE);
+        } else {
+            poll.register(&*self.read, token, Event::READ);
+        }
+    }
+
+    // Copies as much as possible without blocking, returning whether either end has hung up.
+    fn advance(&mut self) -> io::Result<bool> {
+        loop {
+            let res = if self.pos < self.filled {
+                self.write
+                    .write(&self.buf[self.pos..self.filled])
+                    .map(|n| self.pos += n)
+            } else {
+                match self.read.read(&mut self.buf) {
+                    Ok(0) => return Ok(true),
+                    res => res.map(|n| {
+                        self.pos = 0;
+                        self.filled = n;
+                    }),
+                }
+            };
+            match res {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(false),
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(true),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Shuttles data in both directions between two pipe pairs, copying from `a_read` to `b_write`
+/// and from `b_read` to `a_write`, until either side hangs up. This is the core of a
+/// bidirectional tunnel or proxy.
+///
+/// Returns `Ok(())` once a reader reaches end of file or a writer's reading end is closed; data
+/// read from the other direction but not yet written is discarded. Writes that would block are
+/// retried once the writer becomes writable, so both directions make progress independently.
+/// Returns an error of kind [`TimedOut`](io::ErrorKind::TimedOut) if neither direction makes
+/// progress for `timeout`.
+pub fn pump_duplex(
+    a_read: &mut Reader,
+    a_write: &mut Writer,
+    b_read: &mut Reader,
+    b_write: &mut Writer,
+    timeout: Timeout,
+) -> io::Result<()> {
+    let mut halves = [Half::new(a_read, b_write), Half::new(b_read, a_write)];
+    let mut poll = Poll::with_capacity(halves.len());
+    loop {
+        // Bytes already buffered by a reader are invisible to `poll`, so forward them first.
+        for half in &mut halves {
+            if half.read.has_pending() && half.advance()? {
+                return Ok(());
+            }
+        }
+        poll.clear();
+        for (i, half) in halves.iter().enumerate() {
+            half.register(&mut poll, Token(i));
+        }
+        if poll.poll(timeout)? == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out waiting for either side of the tunnel",
+            ));
+        }
+        let mut ready = [false; 2];
+        for (Token(i), _) in poll.events() {
+            ready[i] = true;
+        }
+        for (half, _) in halves.iter_mut().zip(ready).filter(|(_, ready)| *ready) {
+            if half.advance()? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_pump_duplex() {
+        let (mut a_read, mut client_write) = crate::new().unwrap();
+        let (mut client_read, mut a_write) = crate::new().unwrap();
+        let (mut server_read, mut b_write) = crate::new().unwrap();
+        let (mut b_read, mut server_write) = crate::new().unwrap();
+        let pump = thread::spawn(move || {
+            pump_duplex(
+                &mut a_read,
+                &mut a_write,
+                &mut b_read,
+                &mut b_write,
+                Timeout::secs(5).unwrap(),
+            )
+        });
+        for reader in [&client_read, &server_read] {
+            reader.set_nonblocking(false).unwrap();
+        }
+        let mut buf = [0; 5];
+        for _ in 0..3 {
+            client_write.write_all(b"ping?").unwrap();
+            server_read.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"ping?");
+            server_write.write_all(b"pong!").unwrap();
+            client_read.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"pong!");
+        }
+        drop(client_write);
+        pump.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_pump_duplex_pending() {
+        let (mut a_read, mut client_write) = crate::new().unwrap();
+        let (_client_read, mut a_write) = crate::new().unwrap();
+        let (mut server_read, mut b_write) = crate::new().unwrap();
+        let (mut b_read, _server_write) = crate::new().unwrap();
+        client_write.write_all(b"line\nrest").unwrap();
+        let mut line = Vec::new();
+        assert_eq!(a_read.read_until(b'\n', &mut line).unwrap(), Some(5));
+        let pump = thread::spawn(move || {
+            pump_duplex(
+                &mut a_read,
+                &mut a_write,
+                &mut b_read,
+                &mut b_write,
+                Timeout::secs(5).unwrap(),
+            )
+        });
+        server_read.set_nonblocking(false).unwrap();
+        let mut buf = [0; 4];
+        server_read.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"rest");
+        drop(client_write);
+        pump.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_pump_duplex_timeout() {
+        let (mut a_read, _a_peer) = crate::new().unwrap();
+        let (_, mut a_write) = crate::new().unwrap();
+        let (mut b_read, _b_peer) = crate::new().unwrap();
+        let (_b_peer_read, mut b_write) = crate::new().unwrap();
+        let err = pump_duplex(
+            &mut a_read,
+            &mut a_write,
+            &mut b_read,
+            &mut b_write,
+            Timeout::instant(),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
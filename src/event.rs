@@ -5,7 +5,7 @@ use libc::{
 };
 
 bitflags! {
-    /// `Events` is a bit flag set that provides a more type-safe interface for [libc]'s poll flags
+    /// `Event` is a bit flag set that provides a more type-safe interface for [libc]'s poll flags
     /// \([`POLLIN`][libc::POLLIN], [`POLLOUT`][libc::POLLOUT], etc.\).
     pub struct Event: i16 {
         const POLLIN = POLLIN;
@@ -18,6 +18,10 @@ bitflags! {
         const POLLRDBAND = POLLRDBAND;
         const POLLWRNORM = POLLWRNORM;
         const POLLWRBAND = POLLWRBAND;
+        /// The peer has shut down its writing half, e.g. with `shutdown(SHUT_WR)` on a socket.
+        /// Only reported if requested in the interest set. Linux- and Android-specific.
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        const POLLRDHUP = libc::POLLRDHUP;
     }
 }
 
@@ -29,6 +33,22 @@ impl From<Event> for i16 {
 }
 
 impl Event {
+    /// The interest set for reading: all readable events, plus errors and hangups.
+    ///
+    /// Errors and hangups are how `poll` reports that the other end of a pipe has gone away. An
+    /// interest set that leaves them out will still receive them on most platforms, but spelling
+    /// them out makes the intent explicit and ensures they are not forgotten when matching on
+    /// the interest set elsewhere.
+    pub const READ: Event = Event::all_readable()
+        .union(Event::all_error())
+        .union(Event::POLLHUP);
+
+    /// The interest set for writing: all writable events, plus errors. See [`Event::READ`].
+    pub const WRITE: Event = Event::all_writable().union(Event::all_error());
+
+    /// The union of [`Event::READ`] and [`Event::WRITE`].
+    pub const READ_WRITE: Event = Event::READ.union(Event::WRITE);
+
     #[inline]
     #[must_use]
     pub const fn all_readable() -> Event {
@@ -75,10 +95,104 @@ impl Event {
         self.intersects(Event::all_error())
     }
 
-    /// Whether an event includes `Events::POLLHUP`.
+    /// Whether an event includes [`POLLWRBAND`](Event::POLLWRBAND), i.e. priority-band data can
+    /// be written. Anonymous pipes have no priority band, so on Linux this is never reported for
+    /// them.
+    #[inline]
+    #[must_use]
+    pub const fn is_priority_writable(self) -> bool {
+        self.intersects(Event::POLLWRBAND)
+    }
+
+    /// Whether an event includes [`POLLHUP`](Event::POLLHUP).
     #[inline]
     #[must_use]
     pub const fn is_hangup(self) -> bool {
         self.intersects(Event::POLLHUP)
     }
+
+    /// Whether an event indicates that nothing more will be read, because the peer has closed
+    /// its writing half. This is the case for [`POLLHUP`](Event::POLLHUP), which is all a pipe
+    /// ever reports, and on Linux and Android also for `POLLRDHUP`, which a socket reports when only its
+    /// peer's writing half has been shut down.
+    #[inline]
+    #[must_use]
+    pub const fn is_read_hangup(self) -> bool {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let flags = Event::POLLHUP.union(Event::POLLRDHUP);
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let flags = Event::POLLHUP;
+        self.intersects(flags)
+    }
+
+    /// Iterates over the flags set in `self`, yielding each as a single-flag `Event`. Flags are
+    /// guaranteed to be yielded in ascending order of their bit values.
+    pub fn iter(self) -> impl Iterator<Item = Event> {
+        let bits = self.bits;
+        (0..i16::BITS)
+            .map(move |shift| bits & (1 << shift))
+            .filter_map(Event::from_bits)
+            .filter(|ev| !ev.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_priority_writable() {
+        assert!(Event::POLLWRBAND.is_priority_writable());
+        assert!((Event::POLLOUT | Event::POLLWRBAND).is_priority_writable());
+        assert!(
+            !Event::all_writable()
+                .difference(Event::POLLWRBAND)
+                .is_priority_writable()
+        );
+    }
+
+    #[test]
+    fn test_presets() {
+        assert!(Event::READ.contains(Event::all_readable() | Event::all_error() | Event::POLLHUP));
+        assert!(!Event::READ.intersects(Event::all_writable()));
+        assert!(Event::WRITE.contains(Event::all_writable() | Event::all_error()));
+        assert!(!Event::WRITE.intersects(Event::all_readable()));
+        assert_eq!(Event::READ_WRITE, Event::READ | Event::WRITE);
+    }
+
+    #[test]
+    fn test_read_hangup() {
+        let mut poll = crate::Poll::new();
+        let (reader, writer) = crate::new().unwrap();
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        let interest = Event::READ | Event::POLLRDHUP;
+        #[cfg(not(any(target_os = "linux", target_os = "android")))]
+        let interest = Event::READ;
+        poll.register(&reader, crate::Token(0), interest);
+        assert_eq!(poll.poll(crate::Timeout::instant()).unwrap(), 0);
+        drop(writer);
+        assert_eq!(poll.poll(crate::Timeout::instant()).unwrap(), 1);
+        let received = poll.events().fold(Event::empty(), |acc, (_, ev)| acc | ev);
+        assert!(received.is_read_hangup());
+        assert!(!Event::POLLIN.is_read_hangup());
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert!(Event::POLLRDHUP.is_read_hangup());
+    }
+
+    #[test]
+    fn test_iter() {
+        let events = Event::POLLHUP | Event::POLLIN | Event::POLLOUT | Event::POLLERR;
+        let flags: Vec<Event> = events.iter().collect();
+        assert_eq!(
+            flags,
+            vec![
+                Event::POLLIN,
+                Event::POLLOUT,
+                Event::POLLERR,
+                Event::POLLHUP
+            ]
+        );
+        assert!(flags.windows(2).all(|w| w[0].bits() < w[1].bits()));
+        assert_eq!(Event::empty().iter().count(), 0);
+    }
 }
@@ -0,0 +1,193 @@
+//! Support for named pipes (FIFOs), which allow processes that aren't related to each other to
+//! communicate through a path in the filesystem.
+use crate::{Reader, Writer};
+use libc::c_int;
+use std::{
+    convert::TryFrom,
+    ffi::CString,
+    io,
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+};
+
+/// Creates a FIFO at `path` with the permission bits `mode`, which are modified by the process's
+/// umask as usual. Fails with an error of kind [`AlreadyExists`](io::ErrorKind::AlreadyExists)
+/// if something already exists at `path`.
+pub fn create<P: AsRef<Path>>(path: P, mode: u32) -> io::Result<()> {
+    let path = CString::new(path.as_ref().as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mode =
+        libc::mode_t::try_from(mode).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if unsafe { libc::mkfifo(path.as_ptr(), mode) } == -1 {
+        return Err(oserr!());
+    }
+    Ok(())
+}
+
+/// Opens the read end of the FIFO at `path` with the [`O_NONBLOCK`](libc::O_NONBLOCK) and
+/// [`O_CLOEXEC`](libc::O_CLOEXEC) flags set. Unlike the write end, the read end can be opened
+/// before any writer has opened the FIFO; reads then fail with
+/// [`WouldBlock`](io::ErrorKind::WouldBlock) until a writer has connected and written something,
+/// and return `Ok(0)` while no writer is connected at all.
+pub fn open_reader<P: AsRef<Path>>(path: P) -> io::Result<Reader> {
+    let fd = open_fd(path.as_ref(), libc::O_RDONLY)?;
+    Ok(Reader::new(fd))
+}
+
+/// Opens the write end of the FIFO at `path` with the [`O_NONBLOCK`](libc::O_NONBLOCK) and
+/// [`O_CLOEXEC`](libc::O_CLOEXEC) flags set. The writer remembers `path`, so that it can
+/// [reconnect](Writer::reconnect) to a new reader later.
+///
+/// Opening the write end of a FIFO in non-blocking mode fails with `ENXIO` if no process has it
+/// open for reading, which is reported as an error of kind
+/// [`NotConnected`](io::ErrorKind::NotConnected). Use [`Writer::reconnect`] on an existing
+/// writer to wait for a reader instead.
+pub fn open_writer<P: AsRef<Path>>(path: P) -> io::Result<Writer> {
+    let path = path.as_ref();
+    let fd = open_fd(path, libc::O_WRONLY).map_err(|e| match e.raw_os_error() {
+        Some(libc::ENXIO) => io::Error::new(
+            io::ErrorKind::NotConnected,
+            "no process has the FIFO open for reading",
+        ),
+        _ => e,
+    })?;
+    Ok(Writer::with_path(fd, PathBuf::from(path)))
+}
+
+/// Finds a process that has the FIFO or pipe read by `reader` open for writing, by scanning
+/// the file descriptors listed under `/proc`. Returns `None` if no such process is found,
+/// including when the writers belong to processes whose file descriptors cannot be inspected,
+/// e.g. those of other users. If several processes hold the write end, which one is returned is
+/// unspecified; the current process's own id is returned if it holds the write end itself.
+///
+/// Unlike `SO_PEERCRED` on a socket, this is a snapshot rather than a property of the
+/// connection: the writer may have exited, or its file descriptor passed on, by the time the
+/// result is used. Linux-specific.
+#[cfg(target_os = "linux")]
+pub fn peer_pid(reader: &crate::Reader) -> io::Result<Option<u32>> {
+    use std::{fs, os::unix::prelude::*};
+
+    let target = fs::metadata(format!("/proc/self/fd/{}", reader.as_raw_fd()))?;
+    let pids = fs::read_dir("/proc")?
+        .flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok());
+    for pid in pids {
+        // Processes that have exited or belong to other users are skipped.
+        let fds = fs::read_dir(format!("/proc/{pid}/fd"))
+            .into_iter()
+            .flatten()
+            .flatten();
+        for entry in fds {
+            let same_file = fs::metadata(entry.path())
+                .is_ok_and(|meta| meta.dev() == target.dev() && meta.ino() == target.ino());
+            if same_file && is_writable(pid, &entry.file_name().to_string_lossy()) {
+                return Ok(Some(pid));
+            }
+        }
+    }
+    Ok(None)
+}
+
+// Whether the file descriptor `fd` of process `pid` was opened for writing, according to the
+// octal `flags` field of its fdinfo.
+#[cfg(target_os = "linux")]
+fn is_writable(pid: u32, fd: &str) -> bool {
+    let info = std::fs::read_to_string(format!("/proc/{pid}/fdinfo/{fd}")).unwrap_or_default();
+    info.lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|flags| c_int::from_str_radix(flags.trim(), 8).ok())
+        .is_some_and(|flags| matches!(flags & libc::O_ACCMODE, libc::O_WRONLY | libc::O_RDWR))
+}
+
+pub(crate) fn open_fd(path: &Path, access: c_int) -> io::Result<c_int> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    match unsafe { libc::open(path.as_ptr(), access | libc::O_NONBLOCK | libc::O_CLOEXEC) } {
+        -1 => Err(oserr!()),
+        fd => Ok(fd),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Timeout;
+    use std::{
+        io::prelude::*,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn temp_fifo() -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "pipelib-test-{}-{}.fifo",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        create(&path, 0o600).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_open() {
+        let path = temp_fifo();
+        let err = create(&path, 0o600).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        let err = open_writer(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        let mut reader = open_reader(&path).unwrap();
+        let mut writer = open_writer(&path).unwrap();
+        writer.write_all(b"Hello").unwrap();
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_reconnect() {
+        let path = temp_fifo();
+        let mut reader = open_reader(&path).unwrap();
+        let mut writer = open_writer(&path).unwrap();
+        writer.write_all(b"Hello").unwrap();
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        drop(reader);
+        let err = writer.write(b"lost").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        let mut reader = open_reader(&path).unwrap();
+        assert_ok!(writer.reconnect(Timeout::secs(1).unwrap()));
+        writer.write_all(b"world").unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"world");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_peer_pid() {
+        let path = temp_fifo();
+        let reader = open_reader(&path).unwrap();
+        assert_eq!(peer_pid(&reader).unwrap(), None);
+        let writer = open_writer(&path).unwrap();
+        assert_eq!(peer_pid(&reader).unwrap(), Some(std::process::id()));
+        drop(writer);
+        assert_eq!(peer_pid(&reader).unwrap(), None);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_reconnect_timeout() {
+        let path = temp_fifo();
+        let reader = open_reader(&path).unwrap();
+        let mut writer = open_writer(&path).unwrap();
+        drop(reader);
+        let err = writer.reconnect(Timeout::instant()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        std::fs::remove_file(path).unwrap();
+        let (_, mut writer) = crate::new().unwrap();
+        let err = writer.reconnect(Timeout::instant()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,204 @@
+//! Length-delimited messages over a pipe.
+//!
+//! Each message is sent as a frame consisting of a big-endian `u32` length prefix followed by
+//! that many bytes of payload, the same format read by [`Reader::frames`]. Unlike
+//! [`FrameIter`](crate::FrameIter), both ends are non-blocking, so they can be driven from a
+//! [`Poll`](crate::Poll) loop.
+
+use crate::{
+    frames::{take_frame, HEADER_LEN},
+    Reader, Writer,
+};
+use std::{
+    convert::TryFrom,
+    io::{self, prelude::*},
+};
+
+/// Sends length-delimited messages over a pipe. Created by [`Writer::framed`].
+///
+/// Frames are queued in memory when the pipe is full, so a frame is never partially written and
+/// then abandoned. Queued frames are written, in order, by later calls to
+/// [`send`](FramedWriter::send) or [`flush`](FramedWriter::flush).
+#[derive(Debug)]
+pub struct FramedWriter {
+    writer: Writer,
+    queue: Vec<u8>,
+}
+
+impl FramedWriter {
+    pub(crate) fn new(writer: Writer) -> FramedWriter {
+        FramedWriter {
+            writer,
+            queue: Vec::new(),
+        }
+    }
+
+    /// Queues `msg` as a frame and writes as much of the queue as the pipe can take without
+    /// blocking. Succeeds even if part of the queue could not be written yet. Returns an error of
+    /// kind [`InvalidInput`](io::ErrorKind::InvalidInput) if `msg` is too long for its length to
+    /// fit in a `u32`; nothing is queued in that case.
+    pub fn send(&mut self, msg: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(msg.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "message too long to be framed")
+        })?;
+        self.queue.reserve(HEADER_LEN + msg.len());
+        self.queue.extend_from_slice(&len.to_be_bytes());
+        self.queue.extend_from_slice(msg);
+        match self.flush() {
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+            res => res,
+        }
+    }
+
+    /// Writes as much of the queue as the pipe can take without blocking. Returns an error of
+    /// kind [`WouldBlock`](io::ErrorKind::WouldBlock) if the pipe fills up before the queue has
+    /// been written in full.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        let res = loop {
+            if written == self.queue.len() {
+                break Ok(());
+            }
+            match self.writer.write(&self.queue[written..]) {
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.queue.drain(..written);
+        res
+    }
+
+    /// The number of bytes queued but not yet written, including length prefixes.
+    #[inline]
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the underlying [`Writer`]. Any queued frames are discarded.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Writer {
+        self.writer
+    }
+}
+
+/// Receives length-delimited messages from a pipe. Created by [`Reader::framed`].
+///
+/// Frames split across several reads are buffered internally until complete.
+#[derive(Debug)]
+pub struct FramedReader {
+    reader: Reader,
+    max_frame: usize,
+    buf: Vec<u8>,
+}
+
+impl FramedReader {
+    pub(crate) fn new(reader: Reader, max_frame: usize) -> FramedReader {
+        FramedReader {
+            reader,
+            max_frame,
+            buf: Vec::new(),
+        }
+    }
+
+    /// Returns the next complete message, or `None` once the writer has closed the pipe.
+    ///
+    /// Returns an error of kind [`WouldBlock`](io::ErrorKind::WouldBlock) if no complete frame
+    /// is available yet; any partial frame stays buffered for the next call. If the pipe closes
+    /// partway through a frame, an error of kind [`UnexpectedEof`](io::ErrorKind::UnexpectedEof)
+    /// is returned, and a frame whose declared length exceeds the maximum yields an error of
+    /// kind [`InvalidData`](io::ErrorKind::InvalidData).
+    pub fn recv(&mut self) -> io::Result<Option<Vec<u8>>> {
+        const CHUNK_SIZE: usize = 4096;
+        loop {
+            if let Some(frame) = take_frame(&mut self.buf, self.max_frame)? {
+                return Ok(Some(frame));
+            }
+            self.buf.reserve(CHUNK_SIZE);
+            match self.reader.read_into_spare(&mut self.buf) {
+                Ok(0) if self.buf.is_empty() => return Ok(None),
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "pipe closed partway through a frame",
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Returns the underlying [`Reader`]. Any partially received frame is discarded.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frame() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.framed(1024);
+        writer.write_all(&[0, 0, 0, 12, b'H']).unwrap();
+        let err = reader.recv().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        writer.write_all(b"ello, world").unwrap();
+        assert_eq!(reader.recv().unwrap().unwrap(), b"Hello, world");
+        drop(writer);
+        assert_eq!(reader.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn test_back_to_back() {
+        let (reader, writer) = crate::new().unwrap();
+        let (mut reader, mut writer) = (reader.framed(1024), writer.framed());
+        for msg in [&b"Hello"[..], b"", b"world"] {
+            writer.send(msg).unwrap();
+        }
+        assert_eq!(writer.queued(), 0);
+        drop(writer);
+        assert_eq!(reader.recv().unwrap().unwrap(), b"Hello");
+        assert_eq!(reader.recv().unwrap().unwrap(), b"");
+        assert_eq!(reader.recv().unwrap().unwrap(), b"world");
+        assert_eq!(reader.recv().unwrap(), None);
+    }
+
+    #[test]
+    fn test_send_full_pipe() {
+        let (reader, writer) = crate::new().unwrap();
+        let (mut reader, mut writer) = (reader.framed(usize::MAX), writer.framed());
+        let msg = vec![7; 1024 * 1024];
+        writer.send(&msg).unwrap();
+        assert!(writer.queued() > 0);
+        let received = loop {
+            match reader.recv() {
+                Ok(frame) => break frame.unwrap(),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    let _ = writer.flush();
+                }
+                Err(e) => panic!("{}", e),
+            }
+        };
+        assert_eq!(received, msg);
+        assert_eq!(writer.queued(), 0);
+    }
+
+    #[test]
+    fn test_eof_mid_frame() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.framed(1024);
+        writer.write_all(&[0, 0, 0, 12, b'H']).unwrap();
+        drop(writer);
+        let err = reader.recv().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
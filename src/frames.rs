@@ -0,0 +1,171 @@
+use crate::{poll::poll_fd, Event, Reader};
+use std::{convert::TryInto, io, os::unix::prelude::AsRawFd};
+
+/// The size of the big-endian `u32` length prefix preceding each frame.
+pub(crate) const HEADER_LEN: usize = 4;
+
+/// Removes the first complete frame from `buf` and returns its payload, or returns `None` if `buf`
+/// does not hold a complete frame yet.
+pub(crate) fn take_frame(buf: &mut Vec<u8>, max_frame: usize) -> io::Result<Option<Vec<u8>>> {
+    if buf.len() < HEADER_LEN {
+        return Ok(None);
+    }
+    let len = u32::from_be_bytes(buf[..HEADER_LEN].try_into().unwrap()) as usize;
+    if len > max_frame {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame length {len} exceeds maximum of {max_frame}"),
+        ));
+    }
+    if buf.len() < HEADER_LEN + len {
+        return Ok(None);
+    }
+    let frame = buf[HEADER_LEN..HEADER_LEN + len].to_vec();
+    buf.drain(..HEADER_LEN + len);
+    Ok(Some(frame))
+}
+
+/// An iterator over length-delimited frames read from a pipe, created by [`Reader::frames`].
+///
+/// Each frame consists of a big-endian `u32` length prefix followed by that many bytes of
+/// payload. Calls to [`next`](Iterator::next) block, by polling the pipe, until a complete frame
+/// is available, and frames split across several reads are buffered internally. The iterator
+/// ends when the writer closes the pipe, yielding an error of kind
+/// [`UnexpectedEof`](io::ErrorKind::UnexpectedEof) if the pipe closes partway through a frame. A
+/// frame whose declared length exceeds the maximum yields an error of kind
+/// [`InvalidData`](io::ErrorKind::InvalidData). The iterator ends after yielding any error.
+#[derive(Debug)]
+pub struct FrameIter {
+    reader: Reader,
+    max_frame: usize,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl FrameIter {
+    pub(crate) fn new(reader: Reader, max_frame: usize) -> FrameIter {
+        FrameIter {
+            reader,
+            max_frame,
+            buf: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Returns the underlying [`Reader`]. Any partially received frame is discarded.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+
+    fn fill(&mut self) -> io::Result<usize> {
+        const CHUNK_SIZE: usize = 4096;
+        if !self.reader.has_pending() {
+            poll_fd(self.reader.as_raw_fd(), Event::all_readable(), -1)?;
+        }
+        self.buf.reserve(CHUNK_SIZE);
+        self.reader.read_into_spare(&mut self.buf)
+    }
+}
+
+impl Iterator for FrameIter {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match take_frame(&mut self.buf, self.max_frame) {
+                Ok(Some(frame)) => return Some(Ok(frame)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+            match self.fill() {
+                Ok(0) => {
+                    self.done = true;
+                    if self.buf.is_empty() {
+                        return None;
+                    }
+                    return Some(Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "pipe closed in the middle of a frame",
+                    )));
+                }
+                Ok(_) => {}
+                Err(e)
+                    if e.kind() == io::ErrorKind::Interrupted
+                        || e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{convert::TryFrom, io::prelude::*, thread, time::Duration};
+
+    fn frame(payload: &[u8]) -> Vec<u8> {
+        let mut buf = u32::try_from(payload.len()).unwrap().to_be_bytes().to_vec();
+        buf.extend_from_slice(payload);
+        buf
+    }
+
+    #[test]
+    fn test_frames() {
+        let (reader, mut writer) = crate::new().unwrap();
+        writer.write_all(&frame(b"Hello")).unwrap();
+        writer.write_all(&frame(b"world")).unwrap();
+        drop(writer);
+        let frames: Vec<Vec<u8>> = reader.frames(16).map(Result::unwrap).collect();
+        assert_eq!(frames, vec![b"Hello".to_vec(), b"world".to_vec()]);
+    }
+
+    #[test]
+    fn test_frames_partial() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let msg = frame(b"Hello, world");
+        writer.write_all(&msg[..6]).unwrap();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            writer.write_all(&msg[6..]).unwrap();
+        });
+        let mut frames = reader.frames(16);
+        assert_eq!(frames.next().unwrap().unwrap(), b"Hello, world");
+        handle.join().unwrap();
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn test_frames_pending() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"header\n").unwrap();
+        writer.write_all(&frame(b"Hello")).unwrap();
+        let mut line = Vec::new();
+        assert_eq!(reader.read_until(b'\n', &mut line).unwrap(), Some(7));
+        // The frame is buffered by the reader, so polling the pipe would never return.
+        let mut frames = reader.frames(16);
+        assert_eq!(frames.next().unwrap().unwrap(), b"Hello");
+        drop(writer);
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn test_frames_oversized() {
+        let (reader, mut writer) = crate::new().unwrap();
+        writer.write_all(&frame(&[0; 17])).unwrap();
+        let mut frames = reader.frames(16);
+        let err = frames.next().unwrap().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(frames.next().is_none());
+    }
+}
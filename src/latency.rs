@@ -0,0 +1,59 @@
+use crate::{poll::poll_fd, Event, Reader, Writer};
+use std::{
+    io::{self, prelude::*},
+    os::unix::prelude::AsRawFd,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Measures the time taken for a short message to travel from `write` to `read`, which must be
+/// connected, e.g. the two ends of the same pipe. Useful for health checks and for catching IPC
+/// performance regressions.
+///
+/// The message is a timestamp, which is checked once it has been read back; if anything else was
+/// already queued in the pipe, an error of kind [`InvalidData`](io::ErrorKind::InvalidData) is
+/// returned. Blocks until the message has been read.
+pub fn ping_latency(write: &mut Writer, read: &mut Reader) -> io::Result<Duration> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let token = u64::from(nanos).to_be_bytes();
+    let start = Instant::now();
+    write.write_all_blocking(&token)?;
+    let mut buf = [0; 8];
+    let mut received = 0;
+    while received < buf.len() {
+        match read.read(&mut buf[received..]) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => received += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                poll_fd(read.as_raw_fd(), Event::POLLIN, -1)?;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    let elapsed = start.elapsed();
+    if buf != token {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "read back unexpected data while measuring latency",
+        ));
+    }
+    Ok(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ping_latency() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let latency = ping_latency(&mut writer, &mut reader).unwrap();
+        assert!(latency < Duration::from_secs(1));
+        writer.write_all(b"stale data").unwrap();
+        let err = ping_latency(&mut writer, &mut reader).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -7,39 +7,134 @@
 #![doc = include_str!("../README.md")]
 #[macro_use]
 mod macros;
+mod buffered;
+mod builder;
+mod coalesce;
+mod copy;
+mod dispatcher;
+mod duplex;
+#[cfg(all(feature = "epoll", any(target_os = "linux", target_os = "android")))]
+mod epoll;
 mod event;
+pub mod fifo;
+pub mod framed;
+mod frames;
+mod latency;
+mod mirror;
+#[cfg(target_os = "linux")]
+mod namespace;
+mod notifier;
 mod pipe;
 mod poll;
 mod pollable;
+mod pool;
 mod reader;
+#[cfg(target_os = "linux")]
+mod splice;
+mod stream;
+mod tee;
+#[cfg(any(test, feature = "test_support"))]
+mod test_support;
+mod utf8;
 mod writer;
 
 pub use crate::{
+    buffered::BufferedReader,
+    builder::PipeBuilder,
+    coalesce::CoalescingWriter,
+    copy::copy,
+    dispatcher::{Control, Dispatcher},
+    duplex::pump_duplex,
     event::Event,
-    poll::{Poll, Timeout, Token},
+    frames::FrameIter,
+    latency::ping_latency,
+    mirror::MirrorReader,
+    notifier::{Notifier, Waker},
+    pipe::PipeInfo,
+    poll::{EventArray, Poll, PollBuilder, PollResult, StdinGuard, Timeout, Token},
     pollable::Pollable,
+    pool::{PollPool, PooledPoll},
     reader::Reader,
+    stream::{socketpair, Stream},
+    tee::TeeWriter,
+    utf8::Utf8Reader,
     writer::Writer,
 };
-use libc::c_int;
+#[cfg(all(feature = "epoll", any(target_os = "linux", target_os = "android")))]
+pub use crate::epoll::Epoll;
+#[cfg(target_os = "linux")]
+pub use crate::{namespace::new_in_namespace, splice::splice};
+#[cfg(feature = "test_support")]
+pub use crate::test_support::assert_reads;
 
 /// Creates a [`Reader`]/[`Writer`] pair for a non-blocking Unix pipe. The [`FD_CLOEXEC`](libc::FD_CLOEXEC)
-/// and [`O_NONBLOCK`](libc::O_NONBLOCK) flags are set for both.
+/// and [`O_NONBLOCK`](libc::O_NONBLOCK) flags are set for both. Where `pipe2` is available, the
+/// flags are set atomically when the pipe is created, so the file descriptors cannot leak into a
+/// child process spawned concurrently by another thread. To create a pipe with different
+/// options, use a [`PipeBuilder`].
+#[inline]
 pub fn new() -> std::io::Result<(Reader, Writer)> {
-    let mut fds: [c_int; 2] = [-1, -1];
-    unsafe {
-        if libc::pipe(fds.as_mut_ptr()) != 0 {
-            return Err(oserr!());
-        }
-        for fd in fds {
-            if libc::fcntl(fd, libc::FD_CLOEXEC) != 0
-                || libc::fcntl(fd, libc::F_SETFL, libc::O_NONBLOCK) != 0
-            {
-                return Err(oserr!());
-            }
-        }
+    PipeBuilder::new().build()
+}
+
+/// How [`new`] creates pipes on the current platform. See [`creation_method`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CreationMethod {
+    /// The pipe is created with `pipe2`, which sets [`FD_CLOEXEC`](libc::FD_CLOEXEC) and
+    /// [`O_NONBLOCK`](libc::O_NONBLOCK) atomically.
+    Pipe2,
+    /// The pipe is created with `pipe`, and the flags are set afterwards with `fcntl`. A child
+    /// process spawned by another thread in between can inherit the file descriptors.
+    PipeFcntl,
+}
+
+/// Returns how [`new`] creates pipes on the current platform, e.g. to verify that the file
+/// descriptors cannot leak into concurrently spawned child processes.
+#[must_use]
+pub const fn creation_method() -> CreationMethod {
+    if cfg!(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "dragonfly",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )) {
+        CreationMethod::Pipe2
+    } else {
+        CreationMethod::PipeFcntl
     }
-    debug_assert_ne!(fds[0], -1);
-    debug_assert_ne!(fds[1], -1);
-    Ok((Reader::new(fds[0]), Writer::new(fds[1])))
+}
+
+/// Creates `n` pipes as if by calling [`new`] `n` times. If creating any of the pipes fails, all
+/// pipes created up to that point are closed before the error is returned.
+pub fn new_many(n: usize) -> std::io::Result<Vec<(Reader, Writer)>> {
+    new_many_with(n, new)
+}
+
+fn new_many_with(
+    n: usize,
+    mut make_pipe: impl FnMut() -> std::io::Result<(Reader, Writer)>,
+) -> std::io::Result<Vec<(Reader, Writer)>> {
+    let mut pipes = Vec::with_capacity(n);
+    for _ in 0..n {
+        // On error, dropping `pipes` closes every pipe created so far.
+        pipes.push(make_pipe()?);
+    }
+    Ok(pipes)
+}
+
+/// Connects the standard output of `cmd` to a new pipe, returning the non-blocking read end. The
+/// command is only configured, not spawned.
+///
+/// The write end handed to the child is put back into blocking mode, as programs expect. `cmd`
+/// keeps a copy of the write end open until it is dropped, so the reader only sees EOF once
+/// both the child has exited and `cmd` has been dropped.
+pub fn from_command_stdout(cmd: &mut std::process::Command) -> std::io::Result<Reader> {
+    let (reader, writer) = new()?;
+    // The O_NONBLOCK flag belongs to the write end's open file description, so this does not
+    // affect the reader.
+    writer.set_nonblocking(false)?;
+    cmd.stdout(writer);
+    Ok(reader)
 }
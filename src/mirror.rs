@@ -0,0 +1,89 @@
+use crate::Reader;
+use std::{
+    fs::File,
+    io::{self, prelude::*},
+};
+
+/// A [`Reader`] adaptor that writes a copy of everything read from the pipe to a file. Created by
+/// [`Reader::mirror_to`].
+///
+/// By default, a failure to write to the file is returned as an error from `read`. The bytes read
+/// from the pipe in that call are then lost to the caller, as they have already been consumed. In
+/// [best-effort](MirrorReader::set_best_effort) mode, such failures are ignored instead, so the
+/// stream keeps flowing at the cost of gaps in the copy.
+#[derive(Debug)]
+pub struct MirrorReader {
+    reader: Reader,
+    file: File,
+    best_effort: bool,
+}
+
+impl MirrorReader {
+    pub(crate) fn new(reader: Reader, file: File) -> MirrorReader {
+        MirrorReader {
+            reader,
+            file,
+            best_effort: false,
+        }
+    }
+
+    /// Sets whether failures to write to the file are ignored rather than returned from `read`.
+    #[inline]
+    pub fn set_best_effort(&mut self, best_effort: bool) {
+        self.best_effort = best_effort;
+    }
+
+    /// Returns the underlying [`Reader`] and file.
+    #[inline]
+    #[must_use]
+    pub fn into_parts(self) -> (Reader, File) {
+        (self.reader, self.file)
+    }
+}
+
+impl Read for MirrorReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        match self.file.write_all(&buf[..n]) {
+            Err(_) if self.best_effort => {}
+            res => res?,
+        }
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_mirror_to() {
+        let path = env::temp_dir().join(format!("pipelib-mirror-{}", process::id()));
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.mirror_to(File::create(&path).unwrap());
+        writer.write_all(b"Hello, world").unwrap();
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        drop(reader);
+        let mirrored = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(mirrored, b"Hello");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_mirror_to_file_error() {
+        // /dev/full rejects every write with ENOSPC.
+        let file = File::options().write(true).open("/dev/full").unwrap();
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.mirror_to(file);
+        writer.write_all(b"Hello, world").unwrap();
+        let mut buf = [0; 5];
+        assert!(reader.read(&mut buf).is_err());
+        reader.set_best_effort(true);
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b", wor");
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{Reader, Writer};
+use std::{
+    fs::File,
+    io,
+    os::unix::prelude::{AsRawFd, RawFd},
+};
+
+/// Creates a pipe as with [`new`](crate::new), but from within the Linux namespace referred to
+/// by `fd`, e.g. a file opened from `/proc/<pid>/ns/net`. The calling thread joins the namespace
+/// with `setns(2)` just long enough to create the pipe, and then returns to its original
+/// namespace, even if creating the pipe failed.
+///
+/// Joining a namespace generally requires `CAP_SYS_ADMIN`, and mount and user namespaces
+/// cannot be joined by a multithreaded process. Returns an error of kind
+/// [`InvalidInput`](io::ErrorKind::InvalidInput) if `fd` does not refer to a namespace.
+pub fn new_in_namespace(fd: RawFd) -> io::Result<(Reader, Writer)> {
+    let nstype = unsafe { libc::ioctl(fd, libc::NS_GET_NSTYPE) };
+    if nstype == -1 {
+        return Err(oserr!());
+    }
+    let name = match nstype {
+        libc::CLONE_NEWCGROUP => "cgroup",
+        libc::CLONE_NEWIPC => "ipc",
+        libc::CLONE_NEWNET => "net",
+        libc::CLONE_NEWNS => "mnt",
+        libc::CLONE_NEWPID => "pid",
+        libc::CLONE_NEWTIME => "time",
+        libc::CLONE_NEWUSER => "user",
+        libc::CLONE_NEWUTS => "uts",
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "fd does not refer to a known namespace type",
+            ));
+        }
+    };
+    // setns(2) only affects the calling thread, so its own namespace is the one to restore.
+    let original = File::open(format!("/proc/thread-self/ns/{name}"))?;
+    if unsafe { libc::setns(fd, nstype) } == -1 {
+        return Err(oserr!());
+    }
+    let res = crate::new();
+    if unsafe { libc::setns(original.as_raw_fd(), nstype) } == -1 {
+        // Dropping `res` closes the pipe, if it was created.
+        return Err(oserr!());
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::prelude::*;
+
+    #[test]
+    fn test_new_in_namespace() {
+        let ns = File::open("/proc/thread-self/ns/net").unwrap();
+        let (mut reader, mut writer) = match new_in_namespace(ns.as_raw_fd()) {
+            Ok(pipe) => pipe,
+            // Joining a namespace requires CAP_SYS_ADMIN.
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => return,
+            Err(e) => panic!("{}", e),
+        };
+        writer.write_all(b"Hello").unwrap();
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+    }
+
+    #[test]
+    fn test_new_in_namespace_invalid() {
+        let (reader, _writer) = crate::new().unwrap();
+        assert!(new_in_namespace(reader.as_raw_fd()).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use crate::{Reader, Writer};
+use libc::c_void;
+use std::{io, os::unix::prelude::AsRawFd, sync::Arc};
+
+/// A self-pipe for waking a thread blocked in [`Poll::poll`](crate::Poll::poll) from another
+/// thread, e.g. to request a shutdown or to signal that new work has been queued.
+///
+/// Register [`Notifier::reader`] with the `Poll`, hand out [`Waker`]s to other threads, and call
+/// [`Notifier::drain`] after handling a wakeup so that the reader stops being readable.
+#[derive(Debug)]
+pub struct Notifier {
+    reader: Reader,
+    writer: Arc<Writer>,
+}
+
+/// A cheaply clonable handle for waking the thread polling a [`Notifier`]. Created by
+/// [`Notifier::waker`].
+#[derive(Debug, Clone)]
+pub struct Waker {
+    writer: Arc<Writer>,
+}
+
+impl Notifier {
+    /// Creates a notifier backed by a new pipe.
+    pub fn new() -> io::Result<Notifier> {
+        let (reader, writer) = crate::new()?;
+        Ok(Notifier {
+            reader,
+            writer: Arc::new(writer),
+        })
+    }
+
+    /// The read end of the pipe, which becomes readable when a [`Waker`] is woken. Register it
+    /// with a [`Poll`](crate::Poll) for [`Event::READ`](crate::Event::READ).
+    #[inline]
+    #[must_use]
+    pub fn reader(&self) -> &Reader {
+        &self.reader
+    }
+
+    /// Creates a [`Waker`] for this notifier.
+    #[inline]
+    #[must_use]
+    pub fn waker(&self) -> Waker {
+        Waker {
+            writer: Arc::clone(&self.writer),
+        }
+    }
+
+    /// Discards all pending wakeups, returning the number of bytes discarded.
+    pub fn drain(&self) -> io::Result<usize> {
+        let mut buf = [0_u8; 64];
+        let mut drained = 0;
+        loop {
+            let n = unsafe {
+                libc::read(
+                    self.reader.as_raw_fd(),
+                    buf.as_mut_ptr().cast::<c_void>(),
+                    buf.len(),
+                )
+            };
+            match n {
+                0 => return Ok(drained),
+                n if n > 0 => drained += n as usize,
+                _ => {
+                    let e = oserr!();
+                    match e.kind() {
+                        io::ErrorKind::WouldBlock => return Ok(drained),
+                        io::ErrorKind::Interrupted => {}
+                        _ => return Err(e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Waker {
+    /// Wakes the thread polling the notifier's reader by writing a single byte to the pipe. If
+    /// the pipe is full, wakeups are already pending, so the write is skipped; repeated wakeups
+    /// therefore coalesce rather than fail.
+    pub fn wake(&self) -> io::Result<()> {
+        loop {
+            let n = unsafe {
+                libc::write(self.writer.as_raw_fd(), [1_u8].as_ptr().cast::<c_void>(), 1)
+            };
+            if n >= 0 {
+                return Ok(());
+            }
+            let e = oserr!();
+            match e.kind() {
+                io::ErrorKind::WouldBlock => return Ok(()),
+                io::ErrorKind::Interrupted => {}
+                _ => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, Poll, Timeout, Token};
+    use std::{thread, time::Duration};
+
+    #[test]
+    fn test_wake() {
+        let notifier = Notifier::new().unwrap();
+        let waker = notifier.waker();
+        let handle = thread::spawn(move || {
+            let mut poll = Poll::new();
+            poll.register(notifier.reader(), Token(0), Event::READ);
+            let n = poll.poll(Timeout::infinite()).unwrap();
+            (n, notifier)
+        });
+        thread::sleep(Duration::from_millis(20));
+        waker.wake().unwrap();
+        let (n, notifier) = handle.join().unwrap();
+        assert_eq!(n, 1);
+        assert_eq!(notifier.drain().unwrap(), 1);
+        assert_eq!(notifier.drain().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_wake_coalesces() {
+        let notifier = Notifier::new().unwrap();
+        let waker = notifier.waker().clone();
+        // Far more wakeups than the pipe can hold.
+        for _ in 0..100_000 {
+            waker.wake().unwrap();
+        }
+        assert!(notifier.drain().unwrap() > 0);
+        assert_eq!(notifier.drain().unwrap(), 0);
+    }
+}
@@ -1,34 +1,174 @@
 use libc::{self, c_int, c_void, size_t};
+use smallvec::SmallVec;
 use std::{
-    io::{self, prelude::*},
+    convert::TryFrom,
+    io::{self, IoSlice, IoSliceMut, prelude::*},
     os::unix::prelude::{AsRawFd, FromRawFd, IntoRawFd, RawFd},
 };
 
+// IOV_MAX on Linux and macOS. Vectored I/O ignores any further buffers, which is no different
+// from a short read or write.
+const MAX_IOVECS: usize = 1024;
+
 #[derive(Debug)]
 pub(crate) struct Pipe(pub(crate) c_int);
 
+/// A snapshot of the state of a pipe's file descriptor, as returned by
+/// [`Reader::describe`](crate::Reader::describe).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipeInfo {
+    /// The file descriptor number.
+    pub fd: RawFd,
+    /// The capacity of the pipe's kernel buffer in bytes. Only available on Linux.
+    pub capacity: Option<usize>,
+    /// Whether [`O_NONBLOCK`](libc::O_NONBLOCK) is set.
+    pub nonblocking: bool,
+    /// Whether [`FD_CLOEXEC`](libc::FD_CLOEXEC) is set.
+    pub cloexec: bool,
+    /// The number of bytes currently queued in the pipe.
+    pub queued: usize,
+}
+
 impl Pipe {
+    // Both of the following retry if interrupted by a signal before any data was transferred.
+    // If some data had already been transferred, the call instead returns early with a short
+    // count, so it never needs to be retried partway through.
+
     fn write_from_ptr(&mut self, buf: *const c_void, len: usize) -> io::Result<usize> {
-        let written = unsafe { libc::write(self.0, buf, len) };
-        if written < 0 {
-            Err(oserr!())
-        } else {
-            Ok(written as usize)
+        loop {
+            let written = unsafe { libc::write(self.0, buf, len) };
+            if written >= 0 {
+                return Ok(written as usize);
+            }
+            let e = oserr!();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
         }
     }
 
     fn read_to_ptr(&self, buf: *mut c_void, len: usize) -> io::Result<usize> {
-        let bytes_read = unsafe { libc::read(self.0, buf, len as size_t) };
-        if bytes_read < 0 {
+        loop {
+            let bytes_read = unsafe { libc::read(self.0, buf, len as size_t) };
+            if bytes_read >= 0 {
+                return Ok(bytes_read as usize);
+            }
             let e = oserr!();
-            match e.kind() {
-                io::ErrorKind::WouldBlock => Ok(0),
-                _ => Err(e),
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
             }
-        } else {
-            Ok(bytes_read as usize)
         }
     }
+
+    pub(crate) fn read_into_spare(&self, vec: &mut Vec<u8>) -> io::Result<usize> {
+        let spare = vec.spare_capacity_mut();
+        let bytes_read = self.read_to_ptr(spare.as_mut_ptr().cast::<c_void>(), spare.len())?;
+        debug_assert!(bytes_read <= spare.len());
+        // SAFETY: read(2) has initialized the first `bytes_read` bytes of the spare capacity.
+        unsafe { vec.set_len(vec.len() + bytes_read) };
+        Ok(bytes_read)
+    }
+
+    pub(crate) fn stat(&self) -> io::Result<libc::stat> {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        if unsafe { libc::fstat(self.0, stat.as_mut_ptr()) } != 0 {
+            return Err(oserr!());
+        }
+        Ok(unsafe { stat.assume_init() })
+    }
+
+    pub(crate) fn fd_flags(&self) -> io::Result<c_int> {
+        match unsafe { libc::fcntl(self.0, libc::F_GETFD) } {
+            -1 => Err(oserr!()),
+            flags => Ok(flags),
+        }
+    }
+
+    pub(crate) fn status_flags(&self) -> io::Result<c_int> {
+        match unsafe { libc::fcntl(self.0, libc::F_GETFL) } {
+            -1 => Err(oserr!()),
+            flags => Ok(flags),
+        }
+    }
+
+    pub(crate) fn set_status_flag(&self, flag: c_int, enabled: bool) -> io::Result<()> {
+        let flags = self.status_flags()?;
+        let new_flags = if enabled { flags | flag } else { flags & !flag };
+        if new_flags != flags && unsafe { libc::fcntl(self.0, libc::F_SETFL, new_flags) } == -1 {
+            return Err(oserr!());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn close(self) -> io::Result<()> {
+        // Even if close fails, the file descriptor must not be closed again, as its number may
+        // already have been reused.
+        if unsafe { libc::close(self.into_raw_fd()) } == -1 {
+            return Err(oserr!());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn bytes_queued(&self) -> io::Result<usize> {
+        Pipe::bytes_queued_fd(self.0)
+    }
+
+    pub(crate) fn bytes_queued_fd(fd: c_int) -> io::Result<usize> {
+        let mut count: c_int = 0;
+        if unsafe { libc::ioctl(fd, libc::FIONREAD, &mut count) } == -1 {
+            return Err(oserr!());
+        }
+        Ok(count as usize)
+    }
+
+    // Whether `fd` is the write end of a pipe or FIFO, as opposed to a socket, a regular file, or
+    // a FIFO opened for reading.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(crate) fn is_write_end_fd(fd: c_int) -> io::Result<bool> {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+            return Err(oserr!());
+        }
+        if unsafe { stat.assume_init() }.st_mode & libc::S_IFMT != libc::S_IFIFO {
+            return Ok(false);
+        }
+        match unsafe { libc::fcntl(fd, libc::F_GETFL) } {
+            -1 => Err(oserr!()),
+            flags => Ok(flags & libc::O_ACCMODE == libc::O_WRONLY),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn capacity(&self) -> io::Result<usize> {
+        match unsafe { libc::fcntl(self.0, libc::F_GETPIPE_SZ) } {
+            -1 => Err(oserr!()),
+            size => Ok(size as usize),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub(crate) fn set_capacity(&self, bytes: usize) -> io::Result<usize> {
+        let bytes = c_int::try_from(bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "pipe size out of range"))?;
+        match unsafe { libc::fcntl(self.0, libc::F_SETPIPE_SZ, bytes) } {
+            -1 => Err(oserr!()),
+            size => Ok(size as usize),
+        }
+    }
+
+    pub(crate) fn describe(&self) -> io::Result<PipeInfo> {
+        #[cfg(target_os = "linux")]
+        let capacity = Some(self.capacity()?);
+        #[cfg(not(target_os = "linux"))]
+        let capacity = None;
+        Ok(PipeInfo {
+            fd: self.0,
+            capacity,
+            nonblocking: self.status_flags()? & libc::O_NONBLOCK != 0,
+            cloexec: self.fd_flags()? & libc::FD_CLOEXEC != 0,
+            queued: self.bytes_queued()?,
+        })
+    }
 }
 
 impl Write for Pipe {
@@ -38,6 +178,29 @@ impl Write for Pipe {
         self.write_from_ptr(ptr, buf.len())
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        let iovecs: SmallVec<[libc::iovec; 16]> = bufs
+            .iter()
+            .take(MAX_IOVECS)
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_ptr() as *mut c_void,
+                iov_len: buf.len(),
+            })
+            .collect();
+        let count = c_int::try_from(iovecs.len()).unwrap_or(c_int::MAX);
+        // Retried on EINTR like write_from_ptr.
+        loop {
+            let written = unsafe { libc::writev(self.0, iovecs.as_ptr(), count) };
+            if written >= 0 {
+                return Ok(written as usize);
+            }
+            let e = oserr!();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+    }
+
     #[inline]
     fn flush(&mut self) -> io::Result<()> {
         Ok(())
@@ -50,6 +213,29 @@ impl Read for Pipe {
         let ptr = buf.as_mut_ptr().cast::<c_void>();
         self.read_to_ptr(ptr, buf.len())
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        let iovecs: SmallVec<[libc::iovec; 16]> = bufs
+            .iter_mut()
+            .take(MAX_IOVECS)
+            .map(|buf| libc::iovec {
+                iov_base: buf.as_mut_ptr().cast::<c_void>(),
+                iov_len: buf.len(),
+            })
+            .collect();
+        let count = c_int::try_from(iovecs.len()).unwrap_or(c_int::MAX);
+        // Retried on EINTR like read_to_ptr.
+        loop {
+            let bytes_read = unsafe { libc::readv(self.0, iovecs.as_ptr(), count) };
+            if bytes_read >= 0 {
+                return Ok(bytes_read as usize);
+            }
+            let e = oserr!();
+            if e.kind() != io::ErrorKind::Interrupted {
+                return Err(e);
+            }
+        }
+    }
 }
 
 impl FromRawFd for Pipe {
@@ -69,7 +255,9 @@ impl AsRawFd for Pipe {
 impl IntoRawFd for Pipe {
     #[inline]
     fn into_raw_fd(self) -> RawFd {
-        self.as_raw_fd()
+        let fd = self.as_raw_fd();
+        std::mem::forget(self);
+        fd
     }
 }
 
@@ -83,12 +271,102 @@ impl Drop for Pipe {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Pollable;
 
     #[test]
     fn test_new() {
         assert_ok!(crate::new());
     }
 
+    #[test]
+    fn test_new_flags() {
+        let (reader, writer) = crate::new().unwrap();
+        for (fd, access) in [
+            (reader.as_raw_fd(), libc::O_RDONLY),
+            (writer.as_raw_fd(), libc::O_WRONLY),
+        ] {
+            let fd_flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+            assert_ne!(fd_flags & libc::FD_CLOEXEC, 0);
+            let status_flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+            assert_ne!(status_flags & libc::O_NONBLOCK, 0);
+            assert_eq!(status_flags & libc::O_ACCMODE, access);
+        }
+    }
+
+    #[test]
+    fn test_set_nonblocking() {
+        let (reader, writer) = crate::new().unwrap();
+        assert!(reader.is_nonblocking().unwrap());
+        assert!(writer.is_nonblocking().unwrap());
+        reader.set_nonblocking(false).unwrap();
+        writer.set_nonblocking(false).unwrap();
+        assert!(!reader.is_nonblocking().unwrap());
+        assert!(!writer.is_nonblocking().unwrap());
+        reader.set_nonblocking(true).unwrap();
+        assert!(reader.is_nonblocking().unwrap());
+        // Other status flags are preserved.
+        assert_eq!(
+            unsafe { libc::fcntl(writer.as_raw_fd(), libc::F_GETFL) } & libc::O_ACCMODE,
+            libc::O_WRONLY
+        );
+    }
+
+    #[test]
+    fn test_creation_method() {
+        #[cfg(target_os = "linux")]
+        assert_eq!(crate::creation_method(), crate::CreationMethod::Pipe2);
+        #[cfg(target_os = "macos")]
+        assert_eq!(crate::creation_method(), crate::CreationMethod::PipeFcntl);
+    }
+
+    #[test]
+    fn test_from_command_stdout() {
+        let mut cmd = std::process::Command::new("echo");
+        cmd.arg("hello");
+        let mut reader = crate::from_command_stdout(&mut cmd).unwrap();
+        assert!(reader.is_nonblocking().unwrap());
+        let status = cmd.status().unwrap();
+        assert!(status.success());
+        drop(cmd);
+        let mut buf = String::new();
+        reader.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "hello\n");
+    }
+
+    #[test]
+    fn test_new_many() {
+        let mut pipes = crate::new_many(50).unwrap();
+        assert_eq!(pipes.len(), 50);
+        for (i, (reader, writer)) in (0u8..).zip(pipes.iter_mut()) {
+            let mut buf = [0; 1];
+            writer.write_all(&[i]).unwrap();
+            assert_eq!(reader.read(&mut buf).unwrap(), 1);
+            assert_eq!(buf[0], i);
+        }
+    }
+
+    #[test]
+    fn test_new_many_cleanup() {
+        let mut writers = Vec::new();
+        let mut calls = 0;
+        let res = crate::new_many_with(10, || {
+            calls += 1;
+            if calls == 5 {
+                return Err(io::Error::from(io::ErrorKind::Other));
+            }
+            let (reader, writer) = crate::new()?;
+            writers.push(writer.dup()?);
+            Ok((reader, writer))
+        });
+        assert!(res.is_err());
+        assert_eq!(writers.len(), 4);
+        // Every read end created before the failure must have been closed.
+        for mut writer in writers {
+            let err = writer.write(b"x").unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        }
+    }
+
     #[test]
     fn test_read_write() {
         let test_msg = *b"Hello, world";
@@ -99,12 +377,29 @@ mod tests {
         assert_eq!(test_msg, buf);
     }
 
+    #[test]
+    fn test_read_would_block() {
+        let mut buf = [0; 12];
+        let (mut reader, _writer) = crate::new().unwrap();
+        let err = reader.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn test_read_eof() {
+        let mut buf = [0; 12];
+        let (mut reader, writer) = crate::new().unwrap();
+        drop(writer);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
     #[test]
     fn test_read_to_end() {
         let test_msg = b"Hello, world".to_vec();
         let mut buf: Vec<u8> = Vec::with_capacity(6);
         let (mut reader, mut writer) = crate::new().unwrap();
         assert_ok!(writer.write(&test_msg));
+        drop(writer);
         let res = reader.read_to_end(&mut buf);
         assert_ok!(res);
         let bytes_read = res.unwrap();
@@ -112,10 +407,153 @@ mod tests {
         assert_eq!(buf, test_msg);
     }
 
+    #[test]
+    fn test_write_vectored() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let bufs = [
+            IoSlice::new(b"Hello"),
+            IoSlice::new(b", "),
+            IoSlice::new(b"world"),
+        ];
+        assert_eq!(writer.write_vectored(&bufs).unwrap(), 12);
+        let mut buf = [0; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 12);
+        assert_eq!(&buf[..12], b"Hello, world");
+    }
+
+    #[test]
+    fn test_read_vectored() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let msg: Vec<u8> = (0..20).collect();
+        writer.write_all(&msg).unwrap();
+        let (mut header, mut body) = ([0; 8], [0; 12]);
+        let mut bufs = [IoSliceMut::new(&mut header), IoSliceMut::new(&mut body)];
+        assert_eq!(reader.read_vectored(&mut bufs).unwrap(), 20);
+        assert_eq!(
+            reader.read_vectored(&mut bufs).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        drop(writer);
+        assert_eq!(reader.read_vectored(&mut bufs).unwrap(), 0);
+        assert_eq!(header, msg[..8]);
+        assert_eq!(body, msg[8..]);
+    }
+
     #[test]
     fn test_write_all() {
         let test_msg = b"Hello, world".to_vec();
         let (_reader, mut writer) = crate::new().unwrap();
         assert_ok!(writer.write_all(&test_msg));
     }
+
+    #[test]
+    fn test_write_all_partial() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let capacity = reader.describe().unwrap().capacity.unwrap_or(16 * 1024);
+        let filler = vec![0xff; capacity - 8192];
+        writer.write_all(&filler).unwrap();
+        let payload: Vec<u8> = (0..=250).cycle().take(capacity).collect();
+        let err = writer.write_all(&payload).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        drop(writer);
+        let mut received = Vec::new();
+        reader.read_to_end(&mut received).unwrap();
+        assert_eq!(received[..filler.len()], filler[..]);
+        let written = &received[filler.len()..];
+        assert!(!written.is_empty());
+        assert_eq!(written, &payload[..written.len()]);
+    }
+
+    #[test]
+    fn test_write_all_full_pipe() {
+        let (_reader, mut writer) = crate::new().unwrap();
+        let err = writer.write_all(&vec![0; 1024 * 1024]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+    }
+
+    #[test]
+    fn test_read_interrupted() {
+        extern "C" fn handle_signal(_: c_int) {}
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = unsafe { Pipe::from_raw_fd(reader.into_raw_fd()) };
+        reader.set_status_flag(libc::O_NONBLOCK, false).unwrap();
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_signal as extern "C" fn(c_int) as libc::sighandler_t;
+            // No SA_RESTART, so the blocked read(2) fails with EINTR.
+            action.sa_flags = 0;
+            assert_eq!(
+                libc::sigaction(
+                    libc::SIGALRM,
+                    std::ptr::addr_of!(action),
+                    std::ptr::null_mut()
+                ),
+                0
+            );
+        }
+        let reader_thread = unsafe { libc::pthread_self() };
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            unsafe { libc::pthread_kill(reader_thread, libc::SIGALRM) };
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            writer.write_all(b"Hello").unwrap();
+            // Interrupt the blocked readv(2) as well.
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            unsafe { libc::pthread_kill(reader_thread, libc::SIGALRM) };
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            writer.write_all(b"world").unwrap();
+        });
+        let mut buf = [0; 5];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf, b"Hello");
+        let (mut first, mut second) = ([0; 2], [0; 3]);
+        let mut bufs = [IoSliceMut::new(&mut first), IoSliceMut::new(&mut second)];
+        assert_eq!(reader.read_vectored(&mut bufs).unwrap(), 5);
+        assert_eq!((&first, &second), (b"wo", b"rld"));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_write_all_interrupted() {
+        extern "C" fn handle_signal(_: c_int) {}
+        const LEN: usize = 256 * 1024;
+        let (mut reader, writer) = crate::new().unwrap();
+        let mut writer = unsafe { Pipe::from_raw_fd(writer.into_raw_fd()) };
+        writer.set_status_flag(libc::O_NONBLOCK, false).unwrap();
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_signal as extern "C" fn(c_int) as libc::sighandler_t;
+            // No SA_RESTART, so the blocked write(2) fails with EINTR or returns early.
+            action.sa_flags = 0;
+            assert_eq!(
+                libc::sigaction(
+                    libc::SIGUSR1,
+                    std::ptr::addr_of!(action),
+                    std::ptr::null_mut()
+                ),
+                0
+            );
+        }
+        let writer_thread = unsafe { libc::pthread_self() };
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            unsafe { libc::pthread_kill(writer_thread, libc::SIGUSR1) };
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            let mut received = Vec::with_capacity(LEN);
+            let mut buf = [0; 4096];
+            while received.len() < LEN {
+                match reader.read(&mut buf) {
+                    Ok(n) => received.extend_from_slice(&buf[..n]),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        std::thread::sleep(std::time::Duration::from_millis(1));
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            received
+        });
+        let msg: Vec<u8> = (0..=250).cycle().take(LEN).collect();
+        assert_ok!(writer.write_all(&msg));
+        assert_eq!(handle.join().unwrap(), msg);
+    }
 }
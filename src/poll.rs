@@ -1,7 +1,14 @@
-use crate::{Event, Pollable};
+use crate::{pipe::Pipe, Event, Pollable, Reader};
 use libc::{c_int, nfds_t, pollfd};
 use smallvec::SmallVec;
-use std::{fmt, io, iter, mem};
+use std::{
+    convert::TryFrom,
+    fmt, io, mem,
+    ops::{Deref, DerefMut},
+    os::unix::prelude::{FromRawFd, IntoRawFd, RawFd},
+    thread,
+    time::{Duration, Instant},
+};
 
 /// `Poll` provides an interface for [`libc::poll`] that allows the monitoring of registered
 /// [`Reader`](crate::Reader) and [`Writer`](crate::Writer) instances.
@@ -9,6 +16,11 @@ use std::{fmt, io, iter, mem};
 pub struct Poll {
     fds: SmallVec<[PollFd; Poll::POLL_STACK_CAPACITY]>,
     tokens: SmallVec<[Token; Poll::POLL_STACK_CAPACITY]>,
+    owned: Vec<Pipe>,
+    next_token: usize,
+    accumulate: bool,
+    accumulated: Vec<(Token, Event)>,
+    surface_interrupts: bool,
 }
 
 impl Poll {
@@ -21,34 +33,840 @@ impl Poll {
         Default::default()
     }
 
+    /// Creates a `Poll` with room for at least `capacity` registrations before reallocating.
+    /// Only up to eight registrations are stored inline; reserving space up front avoids moving
+    /// them to the heap partway through registering a known number of objects.
+    #[inline]
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Poll {
+        Poll {
+            fds: SmallVec::with_capacity(capacity),
+            tokens: SmallVec::with_capacity(capacity),
+            ..Default::default()
+        }
+    }
+
+    /// The number of registrations.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.fds.len()
+    }
+
+    /// Whether nothing is registered.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.fds.is_empty()
+    }
+
+    /// The number of registrations that can be held without reallocating.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.fds.capacity().min(self.tokens.capacity())
+    }
+
     /// Register a [Pollable] object for polling. `token` is later yielded by [`Poll::events`] along
     /// with each event to indicate which object the event applies to. Note that a caller may
     /// register multiple different pollable objects with the same token.
     pub fn register<T: Pollable>(&mut self, fd: &T, token: Token, events: Event) {
-        self.fds.push(PollFd::new(fd.as_raw_fd(), events));
+        self.register_raw(fd.as_raw_fd(), token, events);
+    }
+
+    /// Registers an arbitrary file descriptor, such as a socket, an eventfd, or a child process's
+    /// standard output, which need not belong to a [Pollable] object. Otherwise equivalent to
+    /// [`Poll::register`]. The caller is responsible for keeping `fd` open while it is
+    /// registered; once it is closed, it will be reported as [`POLLNVAL`](Event::POLLNVAL), or
+    /// worse, its number may be reused by an unrelated file.
+    pub fn register_raw(&mut self, fd: RawFd, token: Token, events: Event) {
+        self.fds.push(PollFd::new(fd, events));
         self.tokens.push(token);
     }
 
+    /// Replaces every registration with the set built by `build`, in a single step. Events
+    /// received by the last poll but not yet consumed are carried over to registrations of the
+    /// same file descriptor in the new set, restricted to what the new interest set would have
+    /// received, so rebuilding the watch set does not lose readiness that has already been
+    /// reported. [Owned](Poll::register_owned) file descriptors stay owned, whether or not they
+    /// are registered again.
+    pub fn reconfigure(&mut self, build: impl FnOnce(&mut PollBuilder)) {
+        let mut builder = PollBuilder::new();
+        build(&mut builder);
+        for pfd in &mut builder.fds {
+            let interest =
+                Event::from_bits_truncate(pfd.0.events) | Event::all_error() | Event::POLLHUP;
+            if let Some(old) = self.fds.iter().find(|old| old.0.fd == pfd.0.fd) {
+                pfd.0.revents = old.0.revents & i16::from(interest);
+            }
+        }
+        self.fds = builder.fds;
+        self.tokens = builder.tokens;
+        let tokens = &self.tokens;
+        self.accumulated.retain(|(tok, _)| tokens.contains(tok));
+    }
+
+    /// Removes every registration of `fd`, returning whether any were found. If `fd` is
+    /// [owned](Poll::register_owned) by the `Poll`, it is closed.
+    pub fn deregister<T: Pollable>(&mut self, fd: &T) -> bool {
+        self.deregister_fd(fd.as_raw_fd())
+    }
+
+    fn deregister_fd(&mut self, fd: c_int) -> bool {
+        let len = self.fds.len();
+        let mut i = 0;
+        while i < self.fds.len() {
+            if self.fds[i].0.fd == fd {
+                self.fds.remove(i);
+                self.tokens.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        self.owned.retain(|pipe| pipe.0 != fd);
+        let tokens = &self.tokens;
+        self.accumulated.retain(|(tok, _)| tokens.contains(tok));
+        self.fds.len() != len
+    }
+
+    // Removes the registrations made with `token`, leaving any other registrations of the same
+    // file descriptors in place. Owned file descriptors are closed once no registration is left.
+    pub(crate) fn deregister_token(&mut self, token: Token) -> bool {
+        let len = self.fds.len();
+        let mut i = 0;
+        while i < self.fds.len() {
+            if self.tokens[i] == token {
+                self.fds.remove(i);
+                self.tokens.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        let fds = &self.fds;
+        self.owned
+            .retain(|pipe| fds.iter().any(|pfd| pfd.0.fd == pipe.0));
+        self.accumulated.retain(|(tok, _)| *tok != token);
+        self.fds.len() != len
+    }
+
+    /// Removes all registrations, retaining the allocated capacity. All
+    /// [owned](Poll::register_owned) file descriptors are closed.
+    pub fn clear(&mut self) {
+        self.fds.clear();
+        self.tokens.clear();
+        self.owned.clear();
+        self.accumulated.clear();
+    }
+
+    // Returns the `Poll` to the state of a new one, retaining the allocated capacity.
+    pub(crate) fn reset(&mut self) {
+        self.clear();
+        self.next_token = 0;
+        self.accumulate = false;
+        self.surface_interrupts = false;
+    }
+
+    /// Registers a [Pollable] object with an empty interest mask. [`POLLERR`](Event::POLLERR),
+    /// [`POLLHUP`](Event::POLLHUP) and [`POLLNVAL`](Event::POLLNVAL) are always reported by
+    /// `poll`, even when not requested, so this is a cheap way to watch an object purely for
+    /// errors and hangups.
+    #[inline]
+    pub fn register_error_only<T: Pollable>(&mut self, fd: &T, token: Token) {
+        self.register(fd, token, Event::empty());
+    }
+
+    /// Registers a [Pollable] object with a newly allocated token, which is returned. Tokens are
+    /// allocated from a counter that only ever increases, so a token is never handed out twice by
+    /// the same `Poll`, even after the object it was allocated for has been removed. Tokens already
+    /// in use by manually registered objects are skipped.
+    pub fn register_auto<T: Pollable>(&mut self, fd: &T, events: Event) -> Token {
+        let mut token = Token(self.next_token);
+        while self.tokens.contains(&token) {
+            token.0 += 1;
+        }
+        self.next_token = token.0 + 1;
+        self.register(fd, token, events);
+        token
+    }
+
+    /// Adds the events in `extra` to the interest mask of an already registered object, leaving
+    /// the rest of the mask untouched. Returns an error of kind
+    /// [`NotFound`](io::ErrorKind::NotFound) if `fd` is not registered.
+    pub fn add_interest<T: Pollable>(&mut self, fd: &T, extra: Event) -> io::Result<()> {
+        self.update_interest(fd.as_raw_fd(), |events| events | extra)
+    }
+
+    /// Removes the events in `events` from the interest mask of an already registered object,
+    /// leaving the rest of the mask untouched. Returns an error of kind
+    /// [`NotFound`](io::ErrorKind::NotFound) if `fd` is not registered.
+    pub fn remove_interest<T: Pollable>(&mut self, fd: &T, events: Event) -> io::Result<()> {
+        self.update_interest(fd.as_raw_fd(), |current| current - events)
+    }
+
+    /// Replaces the interest mask of every registration of `fd` with `events`, returning whether
+    /// any were found. Events already received for `fd` are left untouched, so they can still be
+    /// read with [`Poll::events`].
+    pub fn modify<T: Pollable>(&mut self, fd: &T, events: Event) -> bool {
+        self.update_interest(fd.as_raw_fd(), |_| events).is_ok()
+    }
+
+    /// Replaces the token of an already registered object with `new_token`, leaving its
+    /// interest mask and any received events untouched, and returns the old token. If `fd` is
+    /// registered more than once, every registration is retagged and the token of the first is
+    /// returned. Returns an error of kind [`NotFound`](io::ErrorKind::NotFound) if `fd` is not
+    /// registered.
+    pub fn retag<T: Pollable>(&mut self, fd: &T, new_token: Token) -> io::Result<Token> {
+        let fd = fd.as_raw_fd();
+        let mut old = None;
+        for (pfd, tok) in self.fds.iter().zip(self.tokens.iter_mut()) {
+            if pfd.0.fd == fd {
+                old.get_or_insert(*tok);
+                *tok = new_token;
+            }
+        }
+        old.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "fd is not registered"))
+    }
+
+    fn update_interest(&mut self, fd: c_int, f: impl Fn(Event) -> Event) -> io::Result<()> {
+        let mut found = false;
+        for pfd in self.fds.iter_mut().filter(|pfd| pfd.0.fd == fd) {
+            pfd.0.events = f(Event::from_bits_truncate(pfd.0.events)).into();
+            found = true;
+        }
+        if found {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "fd is not registered",
+            ))
+        }
+    }
+
+    /// Registers a duplicate of the process's standard input, returning a guard through which both
+    /// the `Poll` and the duplicate can be used. When the guard is dropped, the duplicate is
+    /// deregistered and closed, leaving the real standard input untouched.
+    ///
+    /// Unlike the pipes created by this crate, the duplicate is not put into non-blocking mode:
+    /// the [`O_NONBLOCK`](libc::O_NONBLOCK) flag belongs to the open file description shared by
+    /// all duplicates, so setting it would affect standard input itself, and any other process
+    /// sharing it, e.g. through a terminal. Only read from the duplicate after a poll has
+    /// reported it readable, or reads may block.
+    pub fn register_stdin(&mut self, token: Token) -> io::Result<StdinGuard<'_>> {
+        self.register_stdin_fd(libc::STDIN_FILENO, token)
+    }
+
+    fn register_stdin_fd(&mut self, fd: c_int, token: Token) -> io::Result<StdinGuard<'_>> {
+        let dup = match unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) } {
+            -1 => return Err(oserr!()),
+            dup => dup,
+        };
+        let stdin = Reader::from_raw_fd_preserve_flags(dup).inspect_err(|_| unsafe {
+            libc::close(dup);
+        })?;
+        self.register(&stdin, token, Event::all_readable());
+        Ok(StdinGuard { poll: self, stdin })
+    }
+
+    /// Like [`Poll::register`], but transfers ownership of `fd` to the `Poll`. The file descriptor
+    /// is closed when the `Poll` is dropped or [shut down](Poll::shutdown).
+    pub fn register_owned<T: Pollable + IntoRawFd>(&mut self, fd: T, token: Token, events: Event) {
+        self.register(&fd, token, events);
+        self.owned
+            .push(unsafe { Pipe::from_raw_fd(fd.into_raw_fd()) });
+    }
+
+    /// Creates a new `Poll` with the same registrations, tokens and interest sets, but over
+    /// duplicates of the registered file descriptors, so that the same watch set can be polled
+    /// from another thread. A duplicate shares its open file description with the original,
+    /// including the [`O_NONBLOCK`](libc::O_NONBLOCK) flag, so both `Poll`s observe the same
+    /// readiness. The duplicates have the [`FD_CLOEXEC`](libc::FD_CLOEXEC) flag set and are
+    /// [owned](Poll::register_owned) by the new `Poll`.
+    ///
+    /// Every registration costs one extra file descriptor, even if the same file descriptor is
+    /// registered more than once, which counts towards the process's limit (see
+    /// [`Poll::max_fds`]). Registrations of negative file descriptors, which `poll` ignores, are
+    /// copied as they are.
+    pub fn try_clone_registrations(&self) -> io::Result<Poll> {
+        let mut clone = Poll::with_capacity(self.len());
+        for (pfd, token) in self.fds.iter().zip(&self.tokens) {
+            let events = Event::from_bits_truncate(pfd.0.events);
+            if pfd.0.fd < 0 {
+                clone.register_raw(pfd.0.fd, *token, events);
+                continue;
+            }
+            // On failure, dropping `clone` closes the duplicates made so far.
+            let fd = match unsafe { libc::fcntl(pfd.0.fd, libc::F_DUPFD_CLOEXEC, 0) } {
+                -1 => return Err(oserr!()),
+                fd => fd,
+            };
+            clone.owned.push(unsafe { Pipe::from_raw_fd(fd) });
+            clone.register_raw(fd, *token, events);
+        }
+        Ok(clone)
+    }
+
+    /// Waits for every registered pipe with writable interest to be drained by its reader, then
+    /// removes all registrations and closes all [owned](Poll::register_owned) file descriptors.
+    /// If the pipes have not been drained once `timeout` has elapsed, the registrations are
+    /// removed and the owned file descriptors are closed anyway, but an error of kind
+    /// [`TimedOut`](io::ErrorKind::TimedOut) is returned.
+    ///
+    /// Only the write ends of pipes and FIFOs are waited for; other registered file descriptors,
+    /// such as sockets, are not. The wait relies on `ioctl(FIONREAD)` reporting the bytes queued
+    /// on a pipe's write end, so it is only performed on Linux and Android; elsewhere, this
+    /// removes the registrations and closes the owned file descriptors immediately.
+    pub fn shutdown(&mut self, timeout: Timeout) -> io::Result<()> {
+        const MAX_SLEEP: Duration = Duration::from_millis(50);
+        let deadline = timeout.deadline();
+        let mut sleep = Duration::from_millis(1);
+        // poll(2) has no event for a pipe becoming empty, so the queues are checked periodically.
+        let res = loop {
+            match self.writers_drained() {
+                Ok(true) => break Ok(()),
+                Ok(false) => {}
+                Err(e) => break Err(e),
+            }
+            let now = Instant::now();
+            match deadline {
+                Some(deadline) if now >= deadline => {
+                    break Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "timed out waiting for writers to drain",
+                    ));
+                }
+                Some(deadline) => thread::sleep(sleep.min(deadline - now)),
+                None => thread::sleep(sleep),
+            }
+            sleep = (sleep * 2).min(MAX_SLEEP);
+        };
+        self.clear();
+        res
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn writers_drained(&self) -> io::Result<bool> {
+        for pfd in &self.fds {
+            let (fd, events) = (pfd.0.fd, Event::from_bits_truncate(pfd.0.events));
+            if events.is_writable() && Pipe::is_write_end_fd(fd)? && Pipe::bytes_queued_fd(fd)? > 0
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    #[allow(clippy::unnecessary_wraps, clippy::unused_self)]
+    fn writers_drained(&self) -> io::Result<bool> {
+        Ok(true)
+    }
+
+    /// Returns the registered file descriptors as a slice of [`pollfd`] structures, e.g. for
+    /// passing to a C function. The slice is index-aligned with the registered tokens.
+    #[inline]
+    #[must_use]
+    pub fn as_pollfds(&self) -> &[pollfd] {
+        // SAFETY: PollFd is repr(transparent) over pollfd.
+        unsafe { std::slice::from_raw_parts(self.fds.as_ptr().cast::<pollfd>(), self.fds.len()) }
+    }
+
+    /// Mutable version of [`Poll::as_pollfds`]. Entries may be modified in place, but since the
+    /// slice cannot change length, every entry stays paired with the token it was registered with.
+    #[inline]
+    pub fn as_pollfds_mut(&mut self) -> &mut [pollfd] {
+        // SAFETY: PollFd is repr(transparent) over pollfd.
+        unsafe {
+            std::slice::from_raw_parts_mut(self.fds.as_mut_ptr().cast::<pollfd>(), self.fds.len())
+        }
+    }
+
     /// Polls the registered pipes.
+    #[inline]
     pub fn poll(&mut self, timeout: Timeout) -> io::Result<usize> {
-        unsafe {
+        self.poll_ms(timeout.as_millis())
+    }
+
+    fn poll_ms(&mut self, timeout_ms: c_int) -> io::Result<usize> {
+        let n = unsafe {
             let ptr = self.fds.as_mut_ptr().cast::<pollfd>();
-            match libc::poll(ptr, self.fds.len() as nfds_t, timeout.0) {
-                n if n < 0 => Err(oserr!()),
-                n => Ok(n as usize),
+            match libc::poll(ptr, self.fds.len() as nfds_t, timeout_ms) {
+                n if n < 0 => return Err(self.describe_poll_error(oserr!())),
+                n => n as usize,
+            }
+        };
+        if self.accumulate && n > 0 {
+            self.accumulate_revents();
+        }
+        Ok(n)
+    }
+
+    /// Polls the registered pipes with `ppoll(2)`, which has nanosecond timeout resolution and
+    /// can atomically replace the thread's signal mask for the duration of the call. `None` as
+    /// the timeout blocks indefinitely, and `None` as the signal mask leaves it unchanged.
+    ///
+    /// Unblocking a signal only while polling avoids the race in which a signal arrives after it
+    /// has been checked for but before `poll` is entered, leaving the poll to block regardless.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn ppoll(
+        &mut self,
+        timeout: Option<Duration>,
+        sigmask: Option<&libc::sigset_t>,
+    ) -> io::Result<usize> {
+        let timeout = timeout.map(|timeout| libc::timespec {
+            tv_sec: libc::time_t::try_from(timeout.as_secs()).unwrap_or(libc::time_t::MAX),
+            tv_nsec: timeout.subsec_nanos().into(),
+        });
+        let n = unsafe {
+            libc::ppoll(
+                self.fds.as_mut_ptr().cast::<pollfd>(),
+                self.fds.len() as nfds_t,
+                timeout
+                    .as_ref()
+                    .map_or(std::ptr::null(), std::ptr::from_ref),
+                sigmask.map_or(std::ptr::null(), std::ptr::from_ref),
+            )
+        };
+        if n < 0 {
+            return Err(self.describe_poll_error(oserr!()));
+        }
+        let n = n as usize;
+        if self.accumulate && n > 0 {
+            self.accumulate_revents();
+        }
+        Ok(n)
+    }
+
+    /// Polls the registered pipes, reporting why the call returned. By default, a poll
+    /// interrupted by a signal is retried with the remaining timeout; if
+    /// [`Poll::set_surface_interrupts`] has been enabled, [`PollResult::Interrupted`] is returned
+    /// instead, e.g. so that the caller can check a flag set by a signal handler.
+    pub fn poll_full(&mut self, timeout: Timeout) -> io::Result<PollResult> {
+        let deadline = timeout.deadline();
+        let mut timeout_ms = timeout.as_millis();
+        loop {
+            match self.poll_ms(timeout_ms) {
+                Ok(0) => return Ok(PollResult::Timeout),
+                Ok(n) => return Ok(PollResult::Ready(n)),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {
+                    if self.surface_interrupts {
+                        return Ok(PollResult::Interrupted);
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                timeout_ms = c_int::try_from(remaining.as_millis()).unwrap_or(c_int::MAX);
+            }
+        }
+    }
+
+    /// Sets whether [`Poll::poll_full`] returns [`PollResult::Interrupted`] when interrupted by
+    /// a signal, rather than retrying. Disabled by default.
+    #[inline]
+    pub fn set_surface_interrupts(&mut self, surface: bool) {
+        self.surface_interrupts = surface;
+    }
+
+    /// Polls until an event arrives, `timeout` elapses, or the earliest of `deadlines` passes,
+    /// whichever comes first. Returns the received events, as yielded by [`Poll::events`],
+    /// followed by an empty [`Event`] for each token in `deadlines` whose deadline has passed.
+    /// This allows I/O readiness and timer expiry to be handled in one place.
+    pub fn poll_earliest_deadline(
+        &mut self,
+        deadlines: &[(Token, Instant)],
+        timeout: Timeout,
+    ) -> io::Result<Vec<(Token, Event)>> {
+        let now = Instant::now();
+        let mut timeout_ms = timeout.as_millis();
+        if let Some(earliest) = deadlines.iter().map(|(_, deadline)| *deadline).min() {
+            let until = earliest.saturating_duration_since(now);
+            // Round up so that the deadline has passed by the time poll returns.
+            let until_ms =
+                c_int::try_from(until.as_nanos().div_ceil(1_000_000)).unwrap_or(c_int::MAX);
+            if timeout_ms < 0 || until_ms < timeout_ms {
+                timeout_ms = until_ms;
+            }
+        }
+        self.poll_ms(timeout_ms)?;
+        let mut events: Vec<(Token, Event)> = self.events().collect();
+        let now = Instant::now();
+        events.extend(
+            deadlines
+                .iter()
+                .filter(|(_, deadline)| *deadline <= now)
+                .map(|(tok, _)| (*tok, Event::empty())),
+        );
+        Ok(events)
+    }
+
+    /// Like [`Poll::poll`], but splits the wait into intervals of at most 10ms and calls
+    /// `yield_fn` between them, so that a cooperative scheduler can run while this thread waits.
+    /// Returns as soon as an interval ends with events or the timeout has elapsed. Shorter
+    /// intervals would give the scheduler more chances to run at the cost of more syscalls.
+    pub fn poll_yielding(
+        &mut self,
+        timeout: Timeout,
+        mut yield_fn: impl FnMut(),
+    ) -> io::Result<usize> {
+        const INTERVAL_MS: c_int = 10;
+        let deadline = timeout.deadline();
+        loop {
+            let interval_ms = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    c_int::try_from(remaining.as_millis())
+                        .unwrap_or(c_int::MAX)
+                        .min(INTERVAL_MS)
+                }
+                None => INTERVAL_MS,
+            };
+            let n = self.poll_ms(interval_ms)?;
+            if n > 0 || interval_ms < INTERVAL_MS {
+                return Ok(n);
+            }
+            yield_fn();
+        }
+    }
+
+    /// Returns the soft limit on the number of open file descriptors (`RLIMIT_NOFILE`), which is
+    /// also the maximum number of objects that can be polled at once. Returns [`usize::MAX`] if
+    /// there is no limit.
+    pub fn max_fds() -> io::Result<usize> {
+        let mut limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, std::ptr::addr_of_mut!(limit)) } != 0 {
+            return Err(oserr!());
+        }
+        if limit.rlim_cur == libc::RLIM_INFINITY {
+            return Ok(usize::MAX);
+        }
+        Ok(usize::try_from(limit.rlim_cur).unwrap_or(usize::MAX))
+    }
+
+    // poll(2) fails with a bare EINVAL when more fds are passed than RLIMIT_NOFILE allows.
+    fn describe_poll_error(&self, e: io::Error) -> io::Error {
+        if e.raw_os_error() != Some(libc::EINVAL) {
+            return e;
+        }
+        match Poll::max_fds() {
+            Ok(max) if self.fds.len() > max => io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "cannot poll {} file descriptors, which exceeds RLIMIT_NOFILE ({}); raise \
+                     the limit or register fewer objects",
+                    self.fds.len(),
+                    max
+                ),
+            ),
+            _ => e,
+        }
+    }
+
+    /// Polls a caller-owned array of [`pollfd`] structures directly, without registering them. If
+    /// interrupted by a signal, the call is retried with the remaining time.
+    pub fn poll_borrowed(fds: &mut [pollfd], timeout: Timeout) -> io::Result<usize> {
+        let deadline = timeout.deadline();
+        let mut timeout_ms = timeout.as_millis();
+        loop {
+            match unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as nfds_t, timeout_ms) } {
+                n if n >= 0 => return Ok(n as usize),
+                _ => {
+                    let e = oserr!();
+                    if e.kind() != io::ErrorKind::Interrupted {
+                        return Err(e);
+                    }
+                }
+            }
+            if let Some(deadline) = deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                timeout_ms = c_int::try_from(remaining.as_millis()).unwrap_or(c_int::MAX);
+            }
+        }
+    }
+
+    /// Enables or disables accumulation mode. While enabled, the events received by each call to
+    /// [`Poll::poll`] are OR'd into a per-token set that can be inspected with
+    /// [`Poll::peek_accumulated`]. Accumulated events are cleared whenever the received events are
+    /// drained, e.g. by [`Poll::events`]. Disabling accumulation also clears them.
+    pub fn set_accumulate(&mut self, accumulate: bool) {
+        self.accumulate = accumulate;
+        if !accumulate {
+            self.accumulated.clear();
+        }
+    }
+
+    /// Returns the union of all events received for `token` since the received events were last
+    /// drained. Always empty unless accumulation is enabled with [`Poll::set_accumulate`].
+    #[must_use]
+    pub fn peek_accumulated(&self, token: Token) -> Event {
+        self.accumulated
+            .iter()
+            .find(|(tok, _)| *tok == token)
+            .map_or(Event::empty(), |(_, ev)| *ev)
+    }
+
+    fn accumulate_revents(&mut self) {
+        for (pfd, tok) in self.fds.iter().zip(&self.tokens) {
+            let revents = Event::from_bits_truncate(pfd.0.revents);
+            if revents.is_empty() {
+                continue;
+            }
+            match self.accumulated.iter_mut().find(|(t, _)| t == tok) {
+                Some((_, ev)) => *ev |= revents,
+                None => self.accumulated.push((*tok, revents)),
+            }
+        }
+    }
+
+    /// Blocks until at least one registered object has an event, returning the number of objects
+    /// with events. Unlike `poll(Timeout::infinite())`, this retries if interrupted by a signal,
+    /// and returns an error of kind [`InvalidInput`](io::ErrorKind::InvalidInput) rather than
+    /// blocking forever if nothing is registered.
+    pub fn wait(&mut self) -> io::Result<usize> {
+        if self.fds.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "cannot wait on an empty Poll",
+            ));
+        }
+        loop {
+            match self.poll(Timeout::infinite()) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                res => return res,
             }
         }
     }
 
     /// Iterates over events received in the last call to [`Poll::poll`]. Each event
-    /// is yielded along with the token that the [pollable](Pollable) was registered with.
+    /// is yielded along with the token that the [pollable](Pollable) was registered with. Events
+    /// are yielded one flag at a time, in registration order and then in ascending order of bit
+    /// value (see [`Event::iter`]). Objects that received no events are skipped without being
+    /// decoded.
     #[inline]
     pub fn events(&mut self) -> impl Iterator<Item = (Token, Event)> + '_ {
+        self.accumulated.clear();
         self.fds
             .iter_mut()
             .zip(&self.tokens)
+            .filter(|(pfd, _)| pfd.0.revents != 0)
             .flat_map(|(pfd, tok)| pfd.events().map(move |ev| (*tok, ev)))
     }
+
+    /// Collects the events received in the last call to [`Poll::poll`] into a fixed-size array,
+    /// without allocating. Unlike [`Poll::events`], all flags received by a registration are
+    /// combined into one entry, in registration order.
+    ///
+    /// If more than `M` registrations received events, only the first `M` are returned, and the
+    /// returned flag is `true`. Returned events are consumed, so a subsequent call yields the
+    /// remaining events rather than the same ones again.
+    pub fn events_array<const M: usize>(&mut self) -> (EventArray<M>, bool) {
+        self.accumulated.clear();
+        let mut array = EventArray::new();
+        for (pfd, tok) in self.fds.iter_mut().zip(&self.tokens) {
+            if pfd.0.revents == 0 {
+                continue;
+            }
+            if array.len == M {
+                return (array, true);
+            }
+            let revents = Event::from_bits_truncate(mem::take(&mut pfd.0.revents));
+            array.items[array.len] = (*tok, revents);
+            array.len += 1;
+        }
+        (array, false)
+    }
+
+    /// Collects the events received in the last call to [`Poll::poll`], with one entry per
+    /// token: the flags received by every registration sharing a token are combined, which suits
+    /// tokens that stand for one logical handler over several file descriptors. Tokens appear in
+    /// the order of their first registration to receive events. Returned events are consumed.
+    pub fn events_grouped(&mut self) -> Vec<(Token, Event)> {
+        self.accumulated.clear();
+        let mut grouped: Vec<(Token, Event)> = Vec::new();
+        for (pfd, tok) in self.fds.iter_mut().zip(&self.tokens) {
+            if pfd.0.revents == 0 {
+                continue;
+            }
+            let revents = Event::from_bits_truncate(mem::take(&mut pfd.0.revents));
+            match grouped.iter_mut().find(|(t, _)| t == tok) {
+                Some((_, events)) => *events |= revents,
+                None => grouped.push((*tok, revents)),
+            }
+        }
+        grouped
+    }
+
+    /// Polls the registered pipes, then iterates over at most `max_events` of the received
+    /// events, as yielded by [`Poll::events`]. Events beyond the limit are left in place, to be
+    /// consumed by [`Poll::events`] or discarded by the next poll; as polling is level-triggered,
+    /// objects that are still ready are reported again by the next poll either way. This bounds
+    /// the work done per iteration of an event loop, so that a burst of events cannot starve
+    /// timers or other work.
+    pub fn poll_limited(
+        &mut self,
+        timeout: Timeout,
+        max_events: usize,
+    ) -> io::Result<impl Iterator<Item = (Token, Event)> + '_> {
+        self.poll(timeout)?;
+        self.accumulated.clear();
+        Ok(self
+            .fds
+            .iter_mut()
+            .zip(&self.tokens)
+            .filter(|(pfd, _)| pfd.0.revents != 0)
+            .flat_map(|(pfd, tok)| {
+                std::iter::from_fn(move || {
+                    let ev = Event::from_bits_truncate(pfd.0.revents).iter().next()?;
+                    pfd.0.revents &= !ev.bits();
+                    Some((*tok, ev))
+                })
+            })
+            .take(max_events))
+    }
+
+    /// Sorts the events received in the last call to [`Poll::poll`] into the tokens of objects
+    /// that are readable, writable, and in an error or hangup state, in a single pass. An object
+    /// can appear in several lists, e.g. a reader with buffered data whose writer has closed is
+    /// both readable and hung up. Like [`Poll::events`], this consumes the received events.
+    pub fn ready_split(&mut self) -> (Vec<Token>, Vec<Token>, Vec<Token>) {
+        self.accumulated.clear();
+        let (mut readable, mut writable, mut errored) = (Vec::new(), Vec::new(), Vec::new());
+        for (pfd, tok) in self.fds.iter_mut().zip(&self.tokens) {
+            let revents = Event::from_bits_truncate(mem::take(&mut pfd.0.revents));
+            if revents.is_readable() {
+                readable.push(*tok);
+            }
+            if revents.is_writable() {
+                writable.push(*tok);
+            }
+            if revents.is_error() || revents.is_hangup() {
+                errored.push(*tok);
+            }
+        }
+        (readable, writable, errored)
+    }
+}
+
+/// A fixed-capacity, stack-allocated list of events returned by [`Poll::events_array`].
+/// Dereferences to a slice of the events it holds.
+#[derive(Debug, Clone, Copy)]
+pub struct EventArray<const M: usize> {
+    items: [(Token, Event); M],
+    len: usize,
+}
+
+impl<const M: usize> EventArray<M> {
+    fn new() -> Self {
+        EventArray {
+            items: [(Token(0), Event::empty()); M],
+            len: 0,
+        }
+    }
+}
+
+impl<const M: usize> Deref for EventArray<M> {
+    type Target = [(Token, Event)];
+
+    #[inline]
+    fn deref(&self) -> &[(Token, Event)] {
+        &self.items[..self.len]
+    }
+}
+
+/// The outcome of [`Poll::poll_full`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PollResult {
+    /// The given number of registered objects have events.
+    Ready(usize),
+    /// The timeout elapsed without any events.
+    Timeout,
+    /// The poll was interrupted by a signal. Only returned if
+    /// [`Poll::set_surface_interrupts`] is enabled.
+    Interrupted,
+}
+
+/// Accumulates a new set of registrations for [`Poll::reconfigure`].
+#[derive(Debug)]
+pub struct PollBuilder {
+    fds: SmallVec<[PollFd; Poll::POLL_STACK_CAPACITY]>,
+    tokens: SmallVec<[Token; Poll::POLL_STACK_CAPACITY]>,
+}
+
+impl PollBuilder {
+    fn new() -> PollBuilder {
+        PollBuilder {
+            fds: SmallVec::new(),
+            tokens: SmallVec::new(),
+        }
+    }
+
+    /// Adds a registration to the new set. See [`Poll::register`].
+    pub fn register<T: Pollable>(&mut self, fd: &T, token: Token, events: Event) {
+        self.register_raw(fd.as_raw_fd(), token, events);
+    }
+
+    /// Adds a registration of an arbitrary file descriptor to the new set. See
+    /// [`Poll::register_raw`].
+    pub fn register_raw(&mut self, fd: RawFd, token: Token, events: Event) {
+        self.fds.push(PollFd::new(fd, events));
+        self.tokens.push(token);
+    }
+}
+
+/// A guard returned by [`Poll::register_stdin`]. Dereferences to the [`Poll`] it was created
+/// from, and deregisters and closes the duplicate of standard input when dropped.
+#[derive(Debug)]
+pub struct StdinGuard<'a> {
+    poll: &'a mut Poll,
+    stdin: Reader,
+}
+
+impl StdinGuard<'_> {
+    /// The registered duplicate of standard input.
+    #[inline]
+    pub fn stdin(&mut self) -> &mut Reader {
+        &mut self.stdin
+    }
+}
+
+impl Deref for StdinGuard<'_> {
+    type Target = Poll;
+
+    #[inline]
+    fn deref(&self) -> &Poll {
+        self.poll
+    }
+}
+
+impl DerefMut for StdinGuard<'_> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Poll {
+        self.poll
+    }
+}
+
+impl Drop for StdinGuard<'_> {
+    fn drop(&mut self) {
+        self.poll.deregister(&self.stdin);
+    }
+}
+
+/// Polls a single file descriptor for `events` with a timeout in milliseconds, returning the
+/// events that occurred (empty on timeout).
+pub(crate) fn poll_fd(fd: c_int, events: Event, timeout_ms: c_int) -> io::Result<Event> {
+    let mut pfd = pollfd {
+        fd,
+        events: events.into(),
+        revents: 0,
+    };
+    match unsafe { libc::poll(std::ptr::addr_of_mut!(pfd), 1, timeout_ms) } {
+        n if n < 0 => Err(oserr!()),
+        _ => Ok(Event::from_bits_truncate(pfd.revents)),
+    }
 }
 
 /// Timeout value for [`Poll::poll`](crate::Poll::poll). Can be infinite or a number of
@@ -68,7 +886,7 @@ impl Ord for Timeout {
         match (self, other) {
             (x, y) if x.is_infinite() && y.is_infinite() => std::cmp::Ordering::Equal,
             (x, _) if x.is_infinite() => std::cmp::Ordering::Greater,
-            (_, y) if y.is_infinite() => std::cmp::Ordering::Greater,
+            (_, y) if y.is_infinite() => std::cmp::Ordering::Less,
             (x, y) => x.0.cmp(&y.0),
         }
     }
@@ -118,6 +936,21 @@ impl Timeout {
     pub const fn as_secs(self) -> Option<i32> {
         if self.0 >= 0 { Some(self.0) } else { None }
     }
+
+    /// The timeout in milliseconds, as expected by `poll(2)`. Saturates at [`c_int::MAX`].
+    pub(crate) fn as_millis(self) -> c_int {
+        if self.0 < 0 {
+            -1
+        } else {
+            self.0.saturating_mul(1000)
+        }
+    }
+
+    /// The instant at which a timeout starting now expires, or [`None`] if it is infinite.
+    pub(crate) fn deadline(self) -> Option<Instant> {
+        self.as_secs()
+            .map(|secs| Instant::now() + Duration::from_secs(secs as u64))
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -130,6 +963,42 @@ impl From<Token> for usize {
     }
 }
 
+/// Tokens are `usize`-sized, so this conversion is lossless on 32- and 64-bit platforms.
+impl From<Token> for u64 {
+    #[inline]
+    fn from(tok: Token) -> Self {
+        tok.0 as u64
+    }
+}
+
+/// Fails if `n` does not fit in a `usize`, which is only possible on platforms where `usize` is
+/// narrower than 64 bits.
+impl TryFrom<u64> for Token {
+    type Error = std::num::TryFromIntError;
+
+    #[inline]
+    fn try_from(n: u64) -> Result<Self, Self::Error> {
+        usize::try_from(n).map(Token)
+    }
+}
+
+impl Token {
+    /// Creates a token from a `u32`. This cannot fail on any platform with at least 32-bit
+    /// pointers.
+    #[inline]
+    #[must_use]
+    pub const fn from_u32(n: u32) -> Token {
+        Token(n as usize)
+    }
+
+    /// Converts the token to a `u32`, returning [`None`] if its value is too large.
+    #[inline]
+    #[must_use]
+    pub fn as_u32(self) -> Option<u32> {
+        u32::try_from(self.0).ok()
+    }
+}
+
 #[repr(transparent)]
 pub(crate) struct PollFd(pollfd);
 
@@ -152,30 +1021,17 @@ impl PollFd {
         })
     }
 
+    /// Yields the received events, consuming them, in ascending order of bit value. See
+    /// [`Event::iter`].
     pub fn events(&mut self) -> impl Iterator<Item = Event> {
-        let revents = self.0.revents;
-        self.0.revents = 0;
-        let mut shift = 0;
-        iter::from_fn(move || {
-            loop {
-                if shift < mem::size_of::<Event>() * 8 {
-                    let event = revents & (1 << shift);
-                    shift += 1;
-                    if event != 0 {
-                        return Some(Event::from_bits(event).unwrap());
-                    }
-                } else {
-                    return None;
-                }
-            }
-        })
+        Event::from_bits_truncate(mem::take(&mut self.0.revents)).iter()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::io::prelude::*;
+    use std::{io::prelude::*, os::unix::prelude::AsRawFd};
 
     #[test]
     fn test_pollfd_events() {
@@ -188,6 +1044,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::iter_nth_zero, clippy::unused_io_amount)]
     fn test_poll_events() {
         let mut poll = Poll::new();
         let (reader, mut writer) = crate::new().unwrap();
@@ -209,4 +1066,600 @@ mod tests {
         let (_, ev) = poll.events().nth(0).unwrap();
         assert!(ev.is_readable());
     }
+
+    #[test]
+    fn test_register_auto() {
+        let mut poll = Poll::new();
+        let (reader, writer) = crate::new().unwrap();
+        let (other, _) = crate::new().unwrap();
+        poll.register(&other, Token(1), Event::all_readable());
+        let tokens = [
+            poll.register_auto(&reader, Event::all_readable()),
+            poll.register_auto(&writer, Event::all_writable()),
+            poll.register_auto(&other, Event::all_error()),
+        ];
+        assert_eq!(tokens, [Token(0), Token(2), Token(3)]);
+    }
+
+    #[test]
+    fn test_ready_split() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        let (hungup, _) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.register(&writer, Token(1), Event::all_writable());
+        poll.register(&hungup, Token(2), Event::all_readable());
+        writer.write_all(b"Hello").unwrap();
+        poll.poll(Timeout::instant()).unwrap();
+        let (readable, writable, errored) = poll.ready_split();
+        assert_eq!(readable, vec![Token(0)]);
+        assert_eq!(writable, vec![Token(1)]);
+        assert_eq!(errored, vec![Token(2)]);
+        assert_eq!(poll.events().count(), 0);
+    }
+
+    #[test]
+    fn test_accumulate() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.set_accumulate(true);
+        writer.write_all(b"Hello").unwrap();
+        poll.poll(Timeout::instant()).unwrap();
+        let accumulated = poll.peek_accumulated(Token(0));
+        assert!(accumulated.is_readable() && !accumulated.is_hangup());
+        drop(writer);
+        poll.poll(Timeout::instant()).unwrap();
+        let accumulated = poll.peek_accumulated(Token(0));
+        assert!(accumulated.is_readable() && accumulated.is_hangup());
+        assert!(poll.events().count() > 0);
+        assert!(poll.peek_accumulated(Token(0)).is_empty());
+    }
+
+    #[test]
+    fn test_accumulate_deregister() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.set_accumulate(true);
+        writer.write_all(b"Hello").unwrap();
+        poll.poll(Timeout::instant()).unwrap();
+        assert!(poll.peek_accumulated(Token(0)).is_readable());
+        assert!(poll.deregister(&reader));
+        assert!(poll.peek_accumulated(Token(0)).is_empty());
+    }
+
+    #[test]
+    fn test_token_conversions() {
+        let tok = Token(42);
+        assert_eq!(Token::try_from(u64::from(tok)), Ok(tok));
+        assert_eq!(Token::from_u32(42), tok);
+        assert_eq!(tok.as_u32(), Some(42));
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(Token(usize::MAX).as_u32(), None);
+        #[cfg(target_pointer_width = "32")]
+        assert!(Token::try_from(u64::MAX).is_err());
+    }
+
+    #[test]
+    fn test_timeout_ord() {
+        let finite = Timeout::secs(5).unwrap();
+        assert!(finite < Timeout::infinite());
+        assert!(Timeout::infinite() > finite);
+        assert!(Timeout::instant() < finite);
+        assert_eq!(
+            Timeout::infinite().cmp(&Timeout::infinite()),
+            std::cmp::Ordering::Equal
+        );
+        let timeouts = [Timeout::infinite(), finite, Timeout::instant()];
+        assert_eq!(timeouts.iter().min(), Some(&Timeout::instant()));
+        assert_eq!(timeouts.iter().max(), Some(&Timeout::infinite()));
+    }
+
+    #[test]
+    fn test_timeout_as_millis() {
+        assert_eq!(Timeout::instant().as_millis(), 0);
+        assert_eq!(Timeout::infinite().as_millis(), -1);
+        assert_eq!(Timeout::secs(5).unwrap().as_millis(), 5000);
+        assert_eq!(Timeout::secs(i32::MAX).unwrap().as_millis(), c_int::MAX);
+    }
+
+    #[test]
+    fn test_max_fds() {
+        let max = Poll::max_fds().unwrap();
+        assert!(max > 2);
+        if max > 1 << 20 {
+            return;
+        }
+        let (reader, _writer) = crate::new().unwrap();
+        let mut poll = Poll::new();
+        for _ in 0..=max {
+            poll.register(&reader, Token(0), Event::all_readable());
+        }
+        let err = poll.poll(Timeout::instant()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("RLIMIT_NOFILE"));
+    }
+
+    #[test]
+    fn test_poll_yielding() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            writer.write_all(b"Hello").unwrap();
+            writer
+        });
+        let mut yields = 0;
+        let n = poll
+            .poll_yielding(Timeout::secs(5).unwrap(), || yields += 1)
+            .unwrap();
+        assert_eq!(n, 1);
+        assert!(yields > 1);
+        handle.join().unwrap();
+        assert_eq!(
+            poll.poll_yielding(Timeout::instant(), || panic!()).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_poll_earliest_deadline() {
+        let mut poll = Poll::new();
+        let (reader, _writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        let start = Instant::now();
+        let deadlines = [
+            (Token(1), start + Duration::from_millis(20)),
+            (Token(2), start + Duration::from_secs(30)),
+        ];
+        let events = poll
+            .poll_earliest_deadline(&deadlines, Timeout::secs(5).unwrap())
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(20));
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(events, vec![(Token(1), Event::empty())]);
+    }
+
+    #[test]
+    fn test_poll_borrowed() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let (idle, _idle_writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        let mut fds = [
+            pollfd {
+                fd: idle.as_raw_fd(),
+                events: Event::all_readable().into(),
+                revents: 0,
+            },
+            pollfd {
+                fd: reader.as_raw_fd(),
+                events: Event::all_readable().into(),
+                revents: 0,
+            },
+        ];
+        assert_eq!(
+            Poll::poll_borrowed(&mut fds, Timeout::instant()).unwrap(),
+            1
+        );
+        assert_eq!(fds[0].revents, 0);
+        assert!(Event::from_bits_truncate(fds[1].revents).is_readable());
+    }
+
+    #[test]
+    fn test_deregister() {
+        let mut poll = Poll::new();
+        let mut pipes: Vec<_> = (0..3).map(|_| crate::new().unwrap()).collect();
+        for (i, (reader, _)) in pipes.iter().enumerate() {
+            poll.register(reader, Token(i), Event::all_readable());
+        }
+        assert!(poll.deregister(&pipes[1].0));
+        assert!(!poll.deregister(&pipes[1].0));
+        assert_eq!(poll.fds.len(), poll.tokens.len());
+        for (_, writer) in &mut pipes {
+            writer.write_all(b"Hello").unwrap();
+        }
+        poll.poll(Timeout::instant()).unwrap();
+        let mut tokens: Vec<Token> = poll.events().map(|(tok, _)| tok).collect();
+        tokens.dedup();
+        assert_eq!(tokens, vec![Token(0), Token(2)]);
+    }
+
+    #[test]
+    fn test_clear() {
+        let mut poll = Poll::new();
+        let (reader, writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.register_owned(writer, Token(1), Event::all_writable());
+        poll.clear();
+        assert!(poll.as_pollfds().is_empty());
+        assert!(poll.owned.is_empty());
+    }
+
+    #[test]
+    fn test_register_stdin() {
+        let (stdin, mut writer) = crate::new().unwrap();
+        let flags = unsafe { libc::fcntl(stdin.as_raw_fd(), libc::F_GETFL) };
+        let mut poll = Poll::new();
+        {
+            let mut guard = poll.register_stdin_fd(stdin.as_raw_fd(), Token(0)).unwrap();
+            writer.write_all(b"Hello").unwrap();
+            assert_eq!(guard.poll(Timeout::instant()).unwrap(), 1);
+            assert!(
+                guard
+                    .events()
+                    .all(|(tok, ev)| tok == Token(0) && ev.is_readable())
+            );
+            let mut buf = [0; 5];
+            guard.stdin().read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"Hello");
+        }
+        assert!(poll.as_pollfds().is_empty());
+        assert_eq!(
+            unsafe { libc::fcntl(stdin.as_raw_fd(), libc::F_GETFL) },
+            flags
+        );
+    }
+
+    #[test]
+    fn test_register_error_only() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register_error_only(&reader, Token(0));
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 0);
+        drop(writer);
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        let events: Vec<_> = poll.events().collect();
+        assert_eq!(events, vec![(Token(0), Event::POLLHUP)]);
+    }
+
+    #[test]
+    fn test_as_pollfds() {
+        let mut poll = Poll::new();
+        let (reader, writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.register(&writer, Token(1), Event::all_writable());
+        let pfds = poll.as_pollfds();
+        assert_eq!(pfds.len(), 2);
+        assert_eq!(pfds[0].fd, reader.as_raw_fd());
+        assert_eq!(pfds[1].fd, writer.as_raw_fd());
+        poll.as_pollfds_mut()[0].events = Event::POLLPRI.into();
+        assert_eq!(poll.fds[0].0.events, Event::POLLPRI.into());
+    }
+
+    #[test]
+    fn test_add_remove_interest() {
+        let mut poll = Poll::new();
+        let (reader, _writer) = crate::new().unwrap();
+        let (other, _) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::POLLIN);
+        poll.add_interest(&reader, Event::POLLOUT).unwrap();
+        assert_eq!(
+            poll.fds[0].0.events,
+            (Event::POLLIN | Event::POLLOUT).into()
+        );
+        poll.remove_interest(&reader, Event::POLLIN).unwrap();
+        assert_eq!(poll.fds[0].0.events, Event::POLLOUT.into());
+        let err = poll.add_interest(&other, Event::POLLOUT).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_modify() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        assert!(poll.modify(&reader, Event::all_readable() | Event::all_error()));
+        assert!(!poll.modify(&writer, Event::all_writable()));
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        assert_eq!(
+            poll.as_pollfds()[0].events,
+            (Event::all_readable() | Event::all_error()).into()
+        );
+        assert!(poll.events().any(|(_, ev)| ev.is_readable()));
+    }
+
+    #[test]
+    fn test_events_array() {
+        let mut poll = Poll::new();
+        let mut pipes = crate::new_many(3).unwrap();
+        for (i, (reader, writer)) in pipes.iter_mut().enumerate() {
+            poll.register(reader, Token(i), Event::POLLIN);
+            writer.write_all(b"Hello").unwrap();
+        }
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 3);
+        let (events, truncated) = poll.events_array::<2>();
+        assert!(truncated);
+        assert_eq!(
+            &*events,
+            &[(Token(0), Event::POLLIN), (Token(1), Event::POLLIN)]
+        );
+        let (events, truncated) = poll.events_array::<2>();
+        assert!(!truncated);
+        assert_eq!(&*events, &[(Token(2), Event::POLLIN)]);
+        assert!(poll.events_array::<2>().0.is_empty());
+    }
+
+    #[test]
+    fn test_events_skips_idle() {
+        let mut poll = Poll::new();
+        let (idle, _idle_writer) = crate::new().unwrap();
+        let (_reader, writer) = crate::new().unwrap();
+        // Registering the same idle reader repeatedly avoids running into the fd limit.
+        for i in 0..500 {
+            poll.register(&idle, Token(i), Event::all_readable());
+        }
+        poll.register(&writer, Token(500), Event::POLLOUT);
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        let events: Vec<_> = poll.events().collect();
+        assert_eq!(events, [(Token(500), Event::POLLOUT)]);
+    }
+
+    #[test]
+    fn test_read_preset_hangup() {
+        let mut poll = Poll::new();
+        let (reader, writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        drop(writer);
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        assert!(poll.events().any(|(_, ev)| ev.is_hangup()));
+    }
+
+    #[test]
+    fn test_register_raw() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let (mut typed, mut raw) = (Poll::new(), Poll::new());
+        typed.register(&reader, Token(0), Event::READ);
+        raw.register_raw(reader.as_raw_fd(), Token(0), Event::READ);
+        writer.write_all(b"Hello").unwrap();
+        drop(writer);
+        assert_eq!(typed.poll(Timeout::instant()).unwrap(), 1);
+        assert_eq!(raw.poll(Timeout::instant()).unwrap(), 1);
+        assert_eq!(
+            typed.events().collect::<Vec<_>>(),
+            raw.events().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_retag() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(1), Event::READ);
+        assert_eq!(poll.retag(&reader, Token(99)).unwrap(), Token(1));
+        let err = poll.retag(&writer, Token(2)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        assert!(poll.events().all(|(tok, _)| tok == Token(99)));
+    }
+
+    #[test]
+    fn test_poll_full() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        assert_eq!(
+            poll.poll_full(Timeout::instant()).unwrap(),
+            PollResult::Timeout
+        );
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(
+            poll.poll_full(Timeout::instant()).unwrap(),
+            PollResult::Ready(1)
+        );
+    }
+
+    #[test]
+    fn test_poll_full_interrupted() {
+        extern "C" fn handle_signal(_: c_int) {}
+        unsafe {
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = handle_signal as extern "C" fn(c_int) as libc::sighandler_t;
+            assert_eq!(
+                libc::sigaction(
+                    libc::SIGUSR2,
+                    std::ptr::addr_of!(action),
+                    std::ptr::null_mut()
+                ),
+                0
+            );
+        }
+        let mut poll = Poll::new();
+        let (reader, _writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        poll.set_surface_interrupts(true);
+        let poll_thread = unsafe { libc::pthread_self() };
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            unsafe { libc::pthread_kill(poll_thread, libc::SIGUSR2) };
+        });
+        assert_eq!(
+            poll.poll_full(Timeout::secs(5).unwrap()).unwrap(),
+            PollResult::Interrupted
+        );
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_poll_limited() {
+        let mut poll = Poll::new();
+        let mut pipes = crate::new_many(5).unwrap();
+        for (i, (reader, writer)) in pipes.iter_mut().enumerate() {
+            poll.register(reader, Token(i), Event::POLLIN);
+            writer.write_all(b"Hello").unwrap();
+        }
+        let events: Vec<_> = poll.poll_limited(Timeout::instant(), 2).unwrap().collect();
+        assert_eq!(
+            events,
+            [(Token(0), Event::POLLIN), (Token(1), Event::POLLIN)]
+        );
+        let rest: Vec<_> = poll.events().map(|(tok, _)| tok).collect();
+        assert_eq!(rest, [Token(2), Token(3), Token(4)]);
+    }
+
+    #[test]
+    fn test_try_clone_registrations() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(7), Event::READ);
+        let mut clone = poll.try_clone_registrations().unwrap();
+        assert_eq!(clone.len(), 1);
+        assert_ne!(clone.as_pollfds()[0].fd, reader.as_raw_fd());
+        writer.write_all(b"Hello").unwrap();
+        let handle = thread::spawn(move || {
+            assert_eq!(clone.poll(Timeout::instant()).unwrap(), 1);
+            clone.events().map(|(tok, _)| tok).collect::<Vec<_>>()
+        });
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 1);
+        assert!(poll.events().all(|(tok, _)| tok == Token(7)));
+        let tokens = handle.join().unwrap();
+        assert!(!tokens.is_empty() && tokens.iter().all(|tok| *tok == Token(7)));
+    }
+
+    #[test]
+    fn test_events_grouped() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        let (other_reader, other_writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        poll.register(&other_writer, Token(0), Event::WRITE);
+        poll.register(&other_reader, Token(1), Event::READ);
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 2);
+        let grouped = poll.events_grouped();
+        assert_eq!(grouped.len(), 1);
+        let (tok, events) = grouped[0];
+        assert_eq!(tok, Token(0));
+        assert!(events.is_readable() && events.is_writable());
+        assert!(poll.events_grouped().is_empty());
+    }
+
+    #[test]
+    fn test_reconfigure() {
+        let mut poll = Poll::new();
+        let (r1, mut w1) = crate::new().unwrap();
+        let (r2, mut w2) = crate::new().unwrap();
+        poll.register(&r1, Token(0), Event::READ);
+        poll.register(&r2, Token(1), Event::READ);
+        w1.write_all(b"Hello").unwrap();
+        w2.write_all(b"world").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 2);
+        poll.reconfigure(|builder| {
+            builder.register(&r1, Token(2), Event::READ);
+            builder.register(&w2, Token(3), Event::WRITE);
+        });
+        assert_eq!(poll.len(), 2);
+        let events: Vec<_> = poll.events().collect();
+        assert!(events.contains(&(Token(2), Event::POLLIN)));
+        assert!(events.iter().all(|(tok, _)| *tok == Token(2)));
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut poll = Poll::with_capacity(100);
+        assert!(poll.capacity() >= 100);
+        assert_eq!(poll.len(), 0);
+        assert!(poll.is_empty());
+        let (reader, _writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        assert_eq!(poll.len(), 1);
+        assert!(!poll.is_empty());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_ppoll() {
+        let mut poll = Poll::new();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        let start = Instant::now();
+        assert_eq!(
+            poll.ppoll(Some(Duration::from_millis(10)), None).unwrap(),
+            0
+        );
+        assert!(start.elapsed() >= Duration::from_millis(10));
+        writer.write_all(b"Hello").unwrap();
+        let mut mask: libc::sigset_t = unsafe { mem::zeroed() };
+        unsafe { libc::sigemptyset(std::ptr::addr_of_mut!(mask)) };
+        assert_eq!(poll.ppoll(None, Some(&mask)).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_wait() {
+        let mut poll = Poll::new();
+        assert_eq!(poll.wait().unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::all_readable());
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            writer.write_all(b"Hello").unwrap();
+            writer
+        });
+        assert_eq!(poll.wait().unwrap(), 1);
+        assert!(poll.events().any(|(_, ev)| ev.is_readable()));
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_shutdown() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        let mut poll = Poll::new();
+        poll.register_owned(writer, Token(0), Event::all_writable());
+        let handle = thread::spawn(move || {
+            let mut buf = [0; 5];
+            thread::sleep(Duration::from_millis(50));
+            reader.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"Hello");
+            reader
+        });
+        assert_ok!(poll.shutdown(Timeout::secs(5).unwrap()));
+        let reader = handle.join().unwrap();
+        let mut poll = Poll::new();
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.poll(Timeout::instant()).unwrap();
+        assert!(poll.events().any(|(_, ev)| ev.is_hangup()));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_shutdown_non_pipes() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        // Unread data in the socket's receive queue must not hold up the shutdown.
+        let (stream, mut peer) = crate::socketpair().unwrap();
+        peer.write_all(b"world").unwrap();
+        let efd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) };
+        assert_ne!(efd, -1);
+        let mut poll = Poll::new();
+        poll.register_owned(writer, Token(0), Event::all_writable());
+        poll.register(&stream, Token(1), Event::READ_WRITE);
+        poll.register_raw(efd, Token(2), Event::all_writable());
+        let handle = thread::spawn(move || {
+            let mut buf = [0; 5];
+            thread::sleep(Duration::from_millis(50));
+            reader.read_exact(&mut buf).unwrap();
+        });
+        assert_ok!(poll.shutdown(Timeout::secs(5).unwrap()));
+        handle.join().unwrap();
+        assert_eq!(unsafe { libc::close(efd) }, 0);
+    }
+
+    #[test]
+    fn test_shutdown_timeout() {
+        let (reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        let mut poll = Poll::new();
+        poll.register_owned(writer, Token(0), Event::all_writable());
+        let err = poll.shutdown(Timeout::instant()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        poll.register(&reader, Token(0), Event::all_readable());
+        poll.poll(Timeout::instant()).unwrap();
+        assert!(poll.events().any(|(_, ev)| ev.is_hangup()));
+    }
 }
@@ -16,7 +16,7 @@ pub trait Pollable: Sized + AsRawFd + FromRawFd {
             if new_fd == -1 {
                 return Err(oserr!());
             }
-            if libc::fcntl(new_fd, libc::FD_CLOEXEC) != 0 {
+            if libc::fcntl(new_fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
                 libc::close(new_fd);
                 return Err(oserr!());
             }
@@ -32,7 +32,7 @@ pub trait Pollable: Sized + AsRawFd + FromRawFd {
             if new_fd == -1 {
                 return Err(oserr!());
             }
-            if libc::fcntl(new_fd, libc::FD_CLOEXEC) != 0 {
+            if libc::fcntl(new_fd, libc::F_SETFD, libc::FD_CLOEXEC) == -1 {
                 libc::close(new_fd);
                 return Err(oserr!());
             }
@@ -52,12 +52,23 @@ mod tests {
         assert_ok!(duped);
     }
 
+    #[test]
+    fn test_dup_cloexec() {
+        let (rd, _) = crate::new().unwrap();
+        let duped = rd.dup().unwrap();
+        let flags = unsafe { libc::fcntl(duped.as_raw_fd(), libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
+    }
+
     #[test]
     fn test_dup2() {
         const TEST_FD: c_int = 10;
         let (rd, _) = crate::new().unwrap();
         let duped = rd.dup2(TEST_FD);
         assert_ok!(duped);
-        assert_eq!(duped.unwrap().as_raw_fd(), TEST_FD);
+        let duped = duped.unwrap();
+        assert_eq!(duped.as_raw_fd(), TEST_FD);
+        let flags = unsafe { libc::fcntl(TEST_FD, libc::F_GETFD) };
+        assert_ne!(flags & libc::FD_CLOEXEC, 0);
     }
 }
@@ -0,0 +1,143 @@
+use crate::Poll;
+use std::{
+    cell::RefCell,
+    ops::{Deref, DerefMut},
+};
+
+thread_local! {
+    static FREE: RefCell<Vec<Poll>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A per-thread pool of [`Poll`] instances, for code that performs many short-lived, one-shot
+/// polls. Reusing a `Poll` avoids reallocating its registration storage each time.
+#[derive(Debug)]
+pub struct PollPool;
+
+impl PollPool {
+    /// Takes a `Poll` from the current thread's pool, or creates one if the pool is empty. The
+    /// `Poll` has no registrations, and is returned to the pool when the guard is dropped.
+    #[must_use]
+    pub fn acquire() -> PooledPoll {
+        let poll = FREE
+            .with(|free| free.borrow_mut().pop())
+            .unwrap_or_default();
+        PooledPoll { poll: Some(poll) }
+    }
+
+    /// The number of `Poll` instances waiting to be reused in the current thread's pool.
+    #[must_use]
+    pub fn available() -> usize {
+        FREE.with(|free| free.borrow().len())
+    }
+}
+
+/// A [`Poll`] borrowed from a [`PollPool`]. Dereferences to the `Poll`, which is cleared and
+/// returned to the pool when the guard is dropped. Any [owned](Poll::register_owned) file
+/// descriptors are closed at that point.
+#[derive(Debug)]
+pub struct PooledPoll {
+    // Only `None` while being dropped.
+    poll: Option<Poll>,
+}
+
+impl Deref for PooledPoll {
+    type Target = Poll;
+
+    #[inline]
+    fn deref(&self) -> &Poll {
+        self.poll.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledPoll {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Poll {
+        self.poll.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledPoll {
+    fn drop(&mut self) {
+        if let Some(mut poll) = self.poll.take() {
+            poll.reset();
+            // The pool may already have been destroyed if the thread is exiting.
+            let _ = FREE.try_with(|free| free.borrow_mut().push(poll));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, PollResult, Timeout, Token};
+    use libc::c_int;
+    use std::{io::prelude::*, thread, time::Duration};
+
+    #[test]
+    fn test_reuse() {
+        let pipes = crate::new_many(16).unwrap();
+        let mut ptr = None;
+        for _ in 0..3 {
+            let mut poll = PollPool::acquire();
+            assert!(poll.as_pollfds().is_empty());
+            assert_eq!(PollPool::available(), 0);
+            for (i, (reader, _)) in pipes.iter().enumerate() {
+                poll.register(reader, Token(i), Event::READ);
+            }
+            // The registrations have spilled onto the heap, so an unchanged address means
+            // the same allocation was reused.
+            let addr = poll.as_pollfds().as_ptr();
+            assert_eq!(*ptr.get_or_insert(addr), addr);
+            drop(poll);
+            assert_eq!(PollPool::available(), 1);
+        }
+    }
+
+    #[test]
+    fn test_acquire_many() {
+        let (reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        let mut first = PollPool::acquire();
+        let mut second = PollPool::acquire();
+        first.register(&reader, Token(0), Event::READ);
+        second.register(&reader, Token(1), Event::READ);
+        assert_eq!(first.poll(Timeout::instant()).unwrap(), 1);
+        assert_eq!(second.poll(Timeout::instant()).unwrap(), 1);
+        drop((first, second));
+        assert_eq!(PollPool::available(), 2);
+    }
+
+    #[test]
+    fn test_reset_surface_interrupts() {
+        extern "C" fn handle_signal(_: c_int) {}
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handle_signal as extern "C" fn(c_int) as libc::sighandler_t;
+            assert_eq!(
+                libc::sigaction(
+                    libc::SIGUSR2,
+                    std::ptr::addr_of!(action),
+                    std::ptr::null_mut()
+                ),
+                0
+            );
+        }
+        PollPool::acquire().set_surface_interrupts(true);
+        let mut poll = PollPool::acquire();
+        let (reader, mut writer) = crate::new().unwrap();
+        poll.register(&reader, Token(0), Event::READ);
+        let poll_thread = unsafe { libc::pthread_self() };
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            unsafe { libc::pthread_kill(poll_thread, libc::SIGUSR2) };
+            thread::sleep(Duration::from_millis(50));
+            writer.write_all(b"Hello").unwrap();
+        });
+        // The interruption is retried, as in a new Poll, rather than surfaced.
+        assert_eq!(
+            poll.poll_full(Timeout::secs(5).unwrap()).unwrap(),
+            PollResult::Ready(1)
+        );
+        handle.join().unwrap();
+    }
+}
@@ -1,51 +1,694 @@
 use libc::c_int;
 
-use crate::{pipe::Pipe, Pollable};
+use crate::{
+    framed::FramedReader,
+    pipe::{Pipe, PipeInfo},
+    poll::poll_fd,
+    BufferedReader, Event, FrameIter, MirrorReader, Pollable, Timeout, Utf8Reader,
+};
 use std::{
-    io::{self, prelude::*},
+    convert::TryFrom,
+    fs::File,
+    io::{self, IoSliceMut, prelude::*},
+    mem,
     os::unix::{
-        io::{AsRawFd, FromRawFd, RawFd},
+        io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
         prelude::IntoRawFd,
     },
+    thread,
+    time::{Duration, Instant},
 };
 
 /// The read end of a Unix pipe. Like [`Writer`](crate::Writer), Reader is non-blocking, and the
 /// [`CLOEXEC`](libc::FD_CLOEXEC) flag is set.
 #[derive(Debug)]
-pub struct Reader(Pipe);
+pub struct Reader {
+    pipe: Pipe,
+    // Bytes read from the pipe but not yet returned to the caller, e.g. by `read_until`.
+    pending: Vec<u8>,
+    bytes_read: u64,
+}
 
 impl Pollable for Reader {}
 
 impl FromRawFd for Reader {
     #[inline]
     unsafe fn from_raw_fd(fd: RawFd) -> Self {
-        Reader(Pipe(fd))
+        Reader::new(fd)
     }
 }
 
 impl AsRawFd for Reader {
     #[inline]
     fn as_raw_fd(&self) -> RawFd {
-        self.0.0
+        self.pipe.0
     }
 }
 
+/// Any bytes buffered by [`Reader::read_until`] or [`Reader::seek_to_sentinel`] are discarded;
+/// use [`Reader::into_parts`] to keep them.
 impl IntoRawFd for Reader {
     #[inline]
     fn into_raw_fd(self) -> RawFd {
-        self.as_raw_fd()
+        self.pipe.into_raw_fd()
+    }
+}
+
+impl AsFd for Reader {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the file descriptor stays open for as long as `self` is borrowed.
+        unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+    }
+}
+
+/// Any bytes buffered by [`Reader::read_until`] or [`Reader::seek_to_sentinel`] are discarded;
+/// use [`Reader::into_parts`] to keep them.
+impl From<Reader> for OwnedFd {
+    #[inline]
+    fn from(reader: Reader) -> Self {
+        // SAFETY: `into_raw_fd` relinquishes ownership, so the file descriptor is closed once.
+        unsafe { OwnedFd::from_raw_fd(reader.into_raw_fd()) }
+    }
+}
+
+/// Takes ownership of the file descriptor. Its flags are left unchanged; use
+/// [`Reader::set_nonblocking`] to put it into non-blocking mode if needed.
+impl From<OwnedFd> for Reader {
+    #[inline]
+    fn from(fd: OwnedFd) -> Self {
+        Reader::new(fd.into_raw_fd())
     }
 }
 
 impl Reader {
     pub(crate) fn new(n: c_int) -> Reader {
-        Reader(Pipe(n))
+        Reader {
+            pipe: Pipe(n),
+            pending: Vec::new(),
+            bytes_read: 0,
+        }
+    }
+
+    /// Converts the reader into its file descriptor and the bytes it has read from the pipe but
+    /// not yet returned, as buffered by [`Reader::read_until`] or [`Reader::seek_to_sentinel`].
+    /// Unlike converting it into an [`OwnedFd`] directly, this loses no data.
+    #[must_use]
+    pub fn into_parts(mut self) -> (OwnedFd, Vec<u8>) {
+        let pending = self.take_pending();
+        (self.into(), pending)
+    }
+
+    // Takes the bytes read from the pipe but not yet returned to the caller.
+    #[inline]
+    pub(crate) fn take_pending(&mut self) -> Vec<u8> {
+        mem::take(&mut self.pending)
+    }
+
+    // Whether bytes read from the pipe are buffered in `pending`. `poll` cannot report these,
+    // so anything about to wait for readability has to check this first.
+    #[inline]
+    pub(crate) fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    #[inline]
+    pub(crate) fn count_read(&mut self, res: io::Result<usize>) -> io::Result<usize> {
+        if let Ok(n) = res {
+            self.bytes_read += n as u64;
+        }
+        res
+    }
+
+    /// The number of bytes read from the pipe by this handle, including any not yet returned to
+    /// the caller, e.g. those buffered by [`Reader::read_until`].
+    #[inline]
+    #[must_use]
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
+    /// Resets [`Reader::bytes_read`] to zero.
+    #[inline]
+    pub fn reset_counters(&mut self) {
+        self.bytes_read = 0;
+    }
+
+    /// Wraps `fd` without modifying any of its flags. This is useful for file descriptors shared
+    /// with other processes, such as a terminal, where setting [`O_NONBLOCK`](libc::O_NONBLOCK)
+    /// would affect them as well. The caller is responsible for managing the flags; in
+    /// particular, reads will block if `fd` is in blocking mode.
+    ///
+    /// Unlike [`FromRawFd::from_raw_fd`], `fd` is checked with `fcntl(F_GETFL)` first: an error
+    /// is returned if it is not open, or of kind [`InvalidInput`](io::ErrorKind::InvalidInput) if
+    /// it is not open for reading. On error, `fd` is left open and still belongs to the caller;
+    /// on success, ownership is transferred to the returned `Reader`, which closes it on drop.
+    pub fn from_raw_fd_preserve_flags(fd: RawFd) -> io::Result<Reader> {
+        match unsafe { libc::fcntl(fd, libc::F_GETFL) } {
+            -1 => Err(oserr!()),
+            flags if flags & libc::O_ACCMODE == libc::O_WRONLY => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "file descriptor is not open for reading",
+            )),
+            _ => Ok(Reader::new(fd)),
+        }
+    }
+
+    /// Reads directly into the spare capacity of `vec` without zeroing it first, then extends the
+    /// length of `vec` by the number of bytes read. Only bytes actually written by the kernel
+    /// become part of `vec`; the rest of the spare capacity is left untouched. Reserve capacity
+    /// with [`Vec::reserve`] beforehand, as nothing is read if `vec` has no spare capacity.
+    pub fn read_into_spare(&mut self, vec: &mut Vec<u8>) -> io::Result<usize> {
+        if !self.pending.is_empty() {
+            let n = self.pending.len().min(vec.capacity() - vec.len());
+            vec.extend(self.pending.drain(..n));
+            return Ok(n);
+        }
+        let res = self.pipe.read_into_spare(vec);
+        self.count_read(res)
+    }
+
+    /// Reads until the byte `delim` is found, appending all bytes up to and including it to
+    /// `buf` and returning the number of bytes appended. If the delimiter has not arrived yet,
+    /// `Ok(None)` is returned and any bytes read so far are buffered internally until the next
+    /// call, so a delimiter split across multiple writes is handled correctly. If the writer has
+    /// closed the pipe, any remaining bytes are appended without a delimiter, and `Ok(Some(0))`
+    /// is returned once there are none left. This is the non-blocking, byte-oriented analogue of
+    /// [`BufRead::read_until`].
+    ///
+    /// Bytes buffered internally have already been read from the pipe, so `poll` no longer
+    /// reports them: after a call that returns a line, keep calling `read_until` (or
+    /// [`read`](Read::read)) until it returns `Ok(None)` or
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock) before going back to a poll loop, or the lines
+    /// still buffered will not be seen until more data arrives. For the same reason, converting
+    /// the reader into a raw file descriptor or [`OwnedFd`] while bytes are buffered loses them,
+    /// unless it is converted with [`Reader::into_parts`].
+    /// The same applies to [`Reader::seek_to_sentinel`].
+    pub fn read_until(&mut self, delim: u8, buf: &mut Vec<u8>) -> io::Result<Option<usize>> {
+        const CHUNK_SIZE: usize = 4096;
+        let mut searched = 0;
+        loop {
+            if let Some(i) = self.pending[searched..].iter().position(|&b| b == delim) {
+                let end = searched + i + 1;
+                buf.extend(self.pending.drain(..end));
+                return Ok(Some(end));
+            }
+            searched = self.pending.len();
+            self.pending.reserve(CHUNK_SIZE);
+            let res = self.pipe.read_into_spare(&mut self.pending);
+            match self.count_read(res) {
+                Ok(0) => {
+                    let len = self.pending.len();
+                    buf.append(&mut self.pending);
+                    return Ok(Some(len));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(None),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Reads and discards data until `sentinel` has been read, leaving the reader positioned
+    /// right after it, e.g. to resynchronize with a framed stream after a framing error. Waits for
+    /// more data until `timeout` elapses. Returns whether the sentinel was found; `false` is also
+    /// returned if the write end is closed first.
+    ///
+    /// The sentinel may span several reads. When it is not found, the last `sentinel.len() - 1`
+    /// bytes read are kept, as they may be the start of a sentinel that has not fully arrived.
+    pub fn seek_to_sentinel(&mut self, sentinel: &[u8], timeout: Timeout) -> io::Result<bool> {
+        const CHUNK_SIZE: usize = 4096;
+        if sentinel.is_empty() {
+            return Ok(true);
+        }
+        let deadline = timeout.deadline();
+        loop {
+            if let Some(i) = self
+                .pending
+                .windows(sentinel.len())
+                .position(|window| window == sentinel)
+            {
+                self.pending.drain(..i + sentinel.len());
+                return Ok(true);
+            }
+            let keep = self.pending.len().min(sentinel.len() - 1);
+            self.pending.drain(..self.pending.len() - keep);
+            self.pending.reserve(CHUNK_SIZE);
+            let res = self.pipe.read_into_spare(&mut self.pending);
+            match self.count_read(res) {
+                Ok(0) => return Ok(false),
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    let timeout_ms = match deadline {
+                        Some(deadline) => {
+                            let remaining = deadline.saturating_duration_since(Instant::now());
+                            if remaining.is_zero() {
+                                return Ok(false);
+                            }
+                            c_int::try_from(remaining.as_millis())
+                                .unwrap_or(c_int::MAX)
+                                .max(1)
+                        }
+                        None => -1,
+                    };
+                    poll_fd(self.pipe.0, Event::POLLIN, timeout_ms)?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Discards everything that can be read without blocking, including any bytes already
+    /// buffered by the reader itself, and returns the number of bytes discarded. Useful when the
+    /// pipe is only used to wake up a poll loop and the bytes themselves carry no meaning. Stops
+    /// at end of file as well as once the pipe is empty. Intended for non-blocking readers; on a
+    /// blocking reader, this waits for the writer to close the pipe.
+    pub fn drain(&mut self) -> io::Result<usize> {
+        let mut scratch = [0; 4096];
+        let mut total = mem::take(&mut self.pending).len();
+        loop {
+            let res = self.pipe.read(&mut scratch);
+            match self.count_read(res) {
+                Ok(0) => return Ok(total),
+                Ok(n) => total += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(total),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// The number of bytes that can be read without blocking, as reported by `ioctl(FIONREAD)`,
+    /// including any bytes already buffered by the reader itself.
+    #[inline]
+    pub fn bytes_available(&self) -> io::Result<usize> {
+        Ok(self.pending.len() + self.pipe.bytes_queued()?)
+    }
+
+    /// Waits until at least `n` bytes can be read at once, as reported by `ioctl(FIONREAD)`, or
+    /// until `timeout` elapses. Returns whether the bytes are available. Also returns `false`
+    /// early if the write end is closed before enough bytes have arrived, as no more will.
+    ///
+    /// While some but not all of the bytes have arrived, the pipe is already readable, so the
+    /// queued byte count is rechecked every millisecond rather than waited on with `poll`.
+    pub fn wait_for_bytes(&self, n: usize, timeout: Timeout) -> io::Result<bool> {
+        const RETRY_INTERVAL: Duration = Duration::from_millis(1);
+        let deadline = timeout.deadline();
+        loop {
+            let available = self.bytes_available()?;
+            if available >= n {
+                return Ok(true);
+            }
+            let remaining = match deadline {
+                Some(deadline) => match deadline.saturating_duration_since(Instant::now()) {
+                    remaining if remaining.is_zero() => return Ok(false),
+                    remaining => Some(remaining),
+                },
+                None => None,
+            };
+            let revents = if available == 0 {
+                let timeout_ms = remaining.map_or(-1, |remaining| {
+                    c_int::try_from(remaining.as_millis())
+                        .unwrap_or(c_int::MAX)
+                        .max(1)
+                });
+                poll_fd(self.pipe.0, Event::POLLIN, timeout_ms)?
+            } else {
+                poll_fd(self.pipe.0, Event::empty(), 0)?
+            };
+            if revents.is_hangup() {
+                return Ok(self.bytes_available()? >= n);
+            }
+            if available > 0 {
+                thread::sleep(remaining.map_or(RETRY_INTERVAL, |r| r.min(RETRY_INTERVAL)));
+            }
+        }
+    }
+
+    /// Returns an iterator over frames prefixed with a big-endian `u32` length. Frames whose
+    /// declared length exceeds `max_frame` are rejected. See [`FrameIter`] for details.
+    #[inline]
+    #[must_use]
+    pub fn frames(self, max_frame: usize) -> FrameIter {
+        FrameIter::new(self, max_frame)
+    }
+
+    /// Closes the read end of the pipe, like dropping the reader, but reports any error from
+    /// `close` instead of ignoring it. The file descriptor is released either way. Any data
+    /// buffered by the reader itself is discarded.
+    #[inline]
+    pub fn close(self) -> io::Result<()> {
+        self.pipe.close()
+    }
+
+    /// Creates a new, independent handle to the same pipe, like [`File::try_clone`]. The file
+    /// descriptor is duplicated with the [`FD_CLOEXEC`](libc::FD_CLOEXEC) flag set, so dropping
+    /// either handle leaves the other usable. Data already buffered by this reader, e.g. by
+    /// [`Reader::read_until`], is not shared with the clone.
+    #[inline]
+    pub fn try_clone(&self) -> io::Result<Reader> {
+        self.dup()
+    }
+
+    /// Wraps the reader in a [`FramedReader`], which receives length-delimited messages without
+    /// blocking. Frames whose declared length exceeds `max_frame` are rejected.
+    #[inline]
+    #[must_use]
+    pub fn framed(self, max_frame: usize) -> FramedReader {
+        FramedReader::new(self, max_frame)
+    }
+
+    /// Wraps the reader in a [`BufferedReader`], which implements [`BufRead`].
+    #[inline]
+    #[must_use]
+    pub fn buffered(self) -> BufferedReader {
+        BufferedReader::new(self)
+    }
+
+    /// Wraps the reader in a [`Utf8Reader`], which decodes UTF-8 text split across reads.
+    #[inline]
+    #[must_use]
+    pub fn utf8(self) -> Utf8Reader {
+        Utf8Reader::new(self)
+    }
+
+    /// Wraps the reader in a [`MirrorReader`], which writes a copy of everything read to `file`.
+    #[inline]
+    #[must_use]
+    pub fn mirror_to(self, file: File) -> MirrorReader {
+        MirrorReader::new(self, file)
+    }
+
+    /// Checks whether `self` and `other` refer to the same underlying pipe, e.g. because one is a
+    /// [`dup`](Pollable::dup) of the other. Pipes are identified by the device and inode numbers
+    /// reported by `fstat(2)`.
+    pub fn same_pipe(&self, other: &Reader) -> io::Result<bool> {
+        let (lhs, rhs) = (self.pipe.stat()?, other.pipe.stat()?);
+        Ok(lhs.st_dev == rhs.st_dev && lhs.st_ino == rhs.st_ino)
+    }
+
+    /// Sets the process that receives `SIGIO` and `SIGURG` signals for the pipe, as with
+    /// `fcntl(F_SETOWN)`. A negative `pid` denotes a process group. Combine with
+    /// [`Reader::set_async`] to use signal-driven I/O.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_owner(&self, pid: c_int) -> io::Result<()> {
+        if unsafe { libc::fcntl(self.pipe.0, libc::F_SETOWN, pid) } == -1 {
+            return Err(oserr!());
+        }
+        Ok(())
+    }
+
+    /// Sets or clears the [`O_ASYNC`](libc::O_ASYNC) flag, which causes a signal to be sent to
+    /// the pipe's [owner](Reader::set_owner) whenever it becomes readable. Note that the default
+    /// action of `SIGIO` is to terminate the process.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_async(&self, enabled: bool) -> io::Result<()> {
+        self.pipe.set_status_flag(libc::O_ASYNC, enabled)
+    }
+
+    /// Sets or clears the [`O_NONBLOCK`](libc::O_NONBLOCK) flag, leaving all other status flags
+    /// untouched. Pipes created by this crate start out non-blocking. Note that the flag belongs
+    /// to the open file description, so it is shared with any [duplicates](Pollable::dup).
+    #[inline]
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.pipe.set_status_flag(libc::O_NONBLOCK, nonblocking)
+    }
+
+    /// Whether the [`O_NONBLOCK`](libc::O_NONBLOCK) flag is set.
+    #[inline]
+    pub fn is_nonblocking(&self) -> io::Result<bool> {
+        Ok(self.pipe.status_flags()? & libc::O_NONBLOCK != 0)
+    }
+
+    /// Returns a snapshot of the pipe's file descriptor flags, capacity, and queued byte count.
+    /// Useful for debugging and health monitoring.
+    pub fn describe(&self) -> io::Result<PipeInfo> {
+        self.pipe.describe()
     }
 }
 
 impl Read for Reader {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.0.read(buf)
+        if self.pending.is_empty() {
+            let res = self.pipe.read(buf);
+            return self.count_read(res);
+        }
+        let n = self.pending.len().min(buf.len());
+        buf[..n].copy_from_slice(&self.pending[..n]);
+        self.pending.drain(..n);
+        Ok(n)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        if self.pending.is_empty() {
+            let res = self.pipe.read_vectored(bufs);
+            return self.count_read(res);
+        }
+        let mut n = 0;
+        for buf in bufs {
+            let len = (self.pending.len() - n).min(buf.len());
+            buf[..len].copy_from_slice(&self.pending[n..n + len]);
+            n += len;
+        }
+        self.pending.drain(..n);
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_into_spare() {
+        let test_msg: Vec<u8> = (0..32 * 1024).map(|i| (i % 251) as u8).collect();
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(&test_msg).unwrap();
+        let mut buf = Vec::with_capacity(64 * 1024);
+        buf.extend_from_slice(b"prefix");
+        let res = reader.read_into_spare(&mut buf);
+        assert_ok!(res);
+        assert_eq!(res.unwrap(), test_msg.len());
+        assert_eq!(&buf[..6], b"prefix");
+        assert_eq!(&buf[6..], &test_msg[..]);
+    }
+
+    #[test]
+    fn test_same_pipe() {
+        let (rd, _wr) = crate::new().unwrap();
+        let (other, _other_wr) = crate::new().unwrap();
+        let duped = rd.dup().unwrap();
+        assert!(rd.same_pipe(&duped).unwrap());
+        assert!(!rd.same_pipe(&other).unwrap());
+    }
+
+    #[test]
+    fn test_describe() {
+        let (rd, mut wr) = crate::new().unwrap();
+        wr.write_all(b"Hello").unwrap();
+        let info = rd.describe().unwrap();
+        assert_eq!(info.fd, rd.as_raw_fd());
+        assert!(info.nonblocking);
+        assert!(info.cloexec);
+        assert_eq!(info.queued, 5);
+        #[cfg(target_os = "linux")]
+        assert!(info.capacity.unwrap() > 0);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_set_async() {
+        let (rd, _wr) = crate::new().unwrap();
+        assert_ok!(rd.set_owner(unsafe { libc::getpid() }));
+        assert_ok!(rd.set_async(true));
+        assert_ne!(rd.pipe.status_flags().unwrap() & libc::O_ASYNC, 0);
+        assert_ok!(rd.set_async(false));
+        let flags = rd.pipe.status_flags().unwrap();
+        assert_eq!(flags & libc::O_ASYNC, 0);
+        assert_ne!(flags & libc::O_NONBLOCK, 0);
+    }
+
+    #[test]
+    fn test_from_raw_fd_preserve_flags() {
+        let mut fds: [c_int; 2] = [-1, -1];
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        let flags = unsafe { libc::fcntl(fds[0], libc::F_GETFL) };
+        let fd_flags = unsafe { libc::fcntl(fds[0], libc::F_GETFD) };
+        let err = Reader::from_raw_fd_preserve_flags(fds[1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let rd = Reader::from_raw_fd_preserve_flags(fds[0]).unwrap();
+        let _wr = unsafe { crate::Writer::from_raw_fd(fds[1]) };
+        assert_eq!(rd.pipe.status_flags().unwrap(), flags);
+        assert_eq!(rd.pipe.fd_flags().unwrap(), fd_flags);
+        assert_eq!(flags & libc::O_NONBLOCK, 0);
+        assert!(Reader::from_raw_fd_preserve_flags(-1).is_err());
+    }
+
+    #[test]
+    fn test_read_until() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        let mut buf = Vec::new();
+        wr.write_all(b"Hello, ").unwrap();
+        assert_eq!(rd.read_until(0, &mut buf).unwrap(), None);
+        assert!(buf.is_empty());
+        wr.write_all(b"world\0trailing").unwrap();
+        assert_eq!(rd.read_until(0, &mut buf).unwrap(), Some(13));
+        assert_eq!(buf, b"Hello, world\0");
+        let mut rest = [0; 8];
+        assert_eq!(rd.read(&mut rest).unwrap(), 8);
+        assert_eq!(&rest, b"trailing");
+    }
+
+    #[test]
+    fn test_into_parts() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        wr.write_all(b"Hello\nworld").unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(rd.read_until(b'\n', &mut buf).unwrap(), Some(6));
+        let (fd, pending) = rd.into_parts();
+        assert_eq!(pending, b"world");
+        let mut rd = Reader::from(fd);
+        wr.write_all(b"!").unwrap();
+        let mut rest = [0; 1];
+        assert_eq!(rd.read(&mut rest).unwrap(), 1);
+        assert_eq!(&rest, b"!");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_wait_for_bytes() {
+        let (rd, mut wr) = crate::new().unwrap();
+        assert!(!rd.wait_for_bytes(100, Timeout::instant()).unwrap());
+        let handle = thread::spawn(move || {
+            for _ in 0..2 {
+                thread::sleep(Duration::from_millis(20));
+                wr.write_all(&[0; 50]).unwrap();
+            }
+            wr
+        });
+        assert!(rd.wait_for_bytes(100, Timeout::secs(5).unwrap()).unwrap());
+        assert_eq!(rd.describe().unwrap().queued, 100);
+        drop(handle.join().unwrap());
+        assert!(!rd.wait_for_bytes(101, Timeout::infinite()).unwrap());
+    }
+
+    #[test]
+    fn test_seek_to_sentinel() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        assert!(!rd.seek_to_sentinel(b"SYNC", Timeout::instant()).unwrap());
+        wr.write_all(b"garbage SY").unwrap();
+        assert!(!rd.seek_to_sentinel(b"SYNC", Timeout::instant()).unwrap());
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            wr.write_all(b"NCpayload").unwrap();
+            wr
+        });
+        assert!(
+            rd.seek_to_sentinel(b"SYNC", Timeout::secs(5).unwrap())
+                .unwrap()
+        );
+        let _wr = handle.join().unwrap();
+        let mut buf = [0; 7];
+        rd.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"payload");
+    }
+
+    #[test]
+    fn test_close() {
+        let (rd, mut wr) = crate::new().unwrap();
+        assert_ok!(rd.close());
+        let err = wr.write(b"Hello").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let (rd, mut wr) = crate::new().unwrap();
+        let mut clone = rd.try_clone().unwrap();
+        assert_ne!(clone.as_raw_fd(), rd.as_raw_fd());
+        assert!(clone.same_pipe(&rd).unwrap());
+        drop(rd);
+        wr.write_all(b"Hello").unwrap();
+        let mut buf = [0; 5];
+        clone.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+    }
+
+    #[test]
+    fn test_owned_fd() {
+        let (rd, mut wr) = crate::new().unwrap();
+        let fd = rd.as_raw_fd();
+        assert_eq!(rd.as_fd().as_raw_fd(), fd);
+        let owned = OwnedFd::from(rd);
+        assert_eq!(owned.as_raw_fd(), fd);
+        let mut rd = Reader::from(owned);
+        assert_eq!(rd.as_raw_fd(), fd);
+        wr.write_all(b"Hello").unwrap();
+        let mut buf = [0; 5];
+        rd.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+    }
+
+    #[test]
+    fn test_counters() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        wr.write_all(&[1; 1000]).unwrap();
+        wr.write_all(b"line\n").unwrap();
+        assert_eq!(wr.bytes_written(), 1005);
+        let mut buf = [0; 600];
+        rd.read_exact(&mut buf).unwrap();
+        assert_eq!(rd.bytes_read(), 600);
+        let mut line = Vec::new();
+        assert_eq!(rd.read_until(b'\n', &mut line).unwrap(), Some(405));
+        assert_eq!(rd.bytes_read(), 1005);
+        rd.reset_counters();
+        wr.reset_counters();
+        assert_eq!((rd.bytes_read(), wr.bytes_written()), (0, 0));
+        let bufs = [io::IoSlice::new(b"Hello"), io::IoSlice::new(b"world")];
+        assert_eq!(wr.write_vectored(&bufs).unwrap(), 10);
+        assert_eq!(wr.bytes_written(), 10);
+        assert_eq!(rd.drain().unwrap(), 10);
+        assert_eq!(rd.bytes_read(), 10);
+    }
+
+    #[test]
+    fn test_drain() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        wr.write_all(&[7; 5000]).unwrap();
+        assert_eq!(rd.drain().unwrap(), 5000);
+        let err = rd.read(&mut [0; 16]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        wr.write_all(b"line\nrest").unwrap();
+        let mut line = Vec::new();
+        assert_eq!(rd.read_until(b'\n', &mut line).unwrap(), Some(5));
+        drop(wr);
+        assert_eq!(rd.drain().unwrap(), 4);
+        assert_eq!(rd.drain().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_bytes_available() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        wr.write_all(b"Hello, world").unwrap();
+        assert_eq!(rd.bytes_available().unwrap(), 12);
+        let mut buf = [0; 12];
+        rd.read_exact(&mut buf).unwrap();
+        assert_eq!(rd.bytes_available().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_read_vectored_pending() {
+        let (mut rd, mut wr) = crate::new().unwrap();
+        wr.write_all(b"line\nHello, world").unwrap();
+        let mut line = Vec::new();
+        assert_eq!(rd.read_until(b'\n', &mut line).unwrap(), Some(5));
+        let (mut head, mut tail) = ([0; 5], [0; 16]);
+        let mut bufs = [IoSliceMut::new(&mut head), IoSliceMut::new(&mut tail)];
+        assert_eq!(rd.read_vectored(&mut bufs).unwrap(), 12);
+        assert_eq!(&head, b"Hello");
+        assert_eq!(&tail[..7], b", world");
     }
 }
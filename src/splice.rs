@@ -0,0 +1,69 @@
+use crate::{Reader, Writer};
+use std::{io, os::unix::prelude::AsRawFd, ptr};
+
+/// Moves up to `len` bytes from `src` to `dst` with `splice(2)`, without copying them through
+/// user space, and returns the number of bytes moved. Like the rest of this crate, the transfer
+/// is non-blocking: if `src` is empty or `dst` is full, an error of kind
+/// [`WouldBlock`](io::ErrorKind::WouldBlock) is returned. `Ok(0)` means that `src` has reached
+/// EOF.
+///
+/// Data that `src` has already buffered in user space, e.g. by
+/// [`Reader::read_until`](crate::Reader::read_until), is not moved. The bytes moved count towards
+/// both [`Reader::bytes_read`](crate::Reader::bytes_read) and
+/// [`Writer::bytes_written`](crate::Writer::bytes_written). Returns an error of kind
+/// [`NotConnected`](io::ErrorKind::NotConnected) if `dst` has been
+/// [sealed](crate::Writer::seal).
+pub fn splice(src: &mut Reader, dst: &mut Writer, len: usize) -> io::Result<usize> {
+    dst.check_sealed()?;
+    let moved = unsafe {
+        libc::splice(
+            src.as_raw_fd(),
+            ptr::null_mut(),
+            dst.as_raw_fd(),
+            ptr::null_mut(),
+            len,
+            libc::SPLICE_F_NONBLOCK | libc::SPLICE_F_MOVE,
+        )
+    };
+    let res = if moved < 0 {
+        Err(oserr!())
+    } else {
+        Ok(moved as usize)
+    };
+    src.count_read(dst.count_written(res))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::prelude::*;
+
+    #[test]
+    fn test_splice() {
+        let (mut src_reader, mut src_writer) = crate::new().unwrap();
+        let (mut dst_reader, mut dst_writer) = crate::new().unwrap();
+        let err = splice(&mut src_reader, &mut dst_writer, 64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        src_writer.write_all(b"Hello, world").unwrap();
+        assert_eq!(splice(&mut src_reader, &mut dst_writer, 5).unwrap(), 5);
+        assert_eq!(splice(&mut src_reader, &mut dst_writer, 64).unwrap(), 7);
+        let mut buf = [0; 12];
+        dst_reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello, world");
+        drop(src_writer);
+        assert_eq!(splice(&mut src_reader, &mut dst_writer, 64).unwrap(), 0);
+        assert_eq!(src_reader.bytes_read(), 12);
+        assert_eq!(dst_writer.bytes_written(), 12);
+    }
+
+    #[test]
+    fn test_splice_sealed() {
+        let (mut src_reader, mut src_writer) = crate::new().unwrap();
+        let (_dst_reader, mut dst_writer) = crate::new().unwrap();
+        src_writer.write_all(b"Hello").unwrap();
+        dst_writer.seal();
+        let err = splice(&mut src_reader, &mut dst_writer, 64).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotConnected);
+        assert_eq!(src_reader.bytes_read(), 0);
+    }
+}
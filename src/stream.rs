@@ -0,0 +1,200 @@
+use crate::{pipe::Pipe, Pollable};
+use libc::c_int;
+use std::{
+    io::{self, prelude::*},
+    os::unix::{
+        io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd},
+        prelude::OwnedFd,
+    },
+};
+
+/// One end of a bidirectional channel created by [`socketpair`]. Like [`Reader`](crate::Reader)
+/// and [`Writer`](crate::Writer), a `Stream` is non-blocking, and the
+/// [`CLOEXEC`](libc::FD_CLOEXEC) flag is set. Both ends can be read from and written to, so a
+/// single registration can wait for both [readability](crate::Event::READ) and
+/// [writability](crate::Event::WRITE).
+#[derive(Debug)]
+pub struct Stream(Pipe);
+
+/// Creates a connected pair of Unix domain stream sockets, as a full-duplex alternative to a
+/// pair of pipes. Data written to either end is read from the other.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub fn socketpair() -> io::Result<(Stream, Stream)> {
+    let ty = libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC;
+    let mut fds: [c_int; 2] = [-1, -1];
+    if unsafe { libc::socketpair(libc::AF_UNIX, ty, 0, fds.as_mut_ptr()) } != 0 {
+        return Err(oserr!());
+    }
+    debug_assert_ne!(fds[0], -1);
+    debug_assert_ne!(fds[1], -1);
+    Ok((Stream(Pipe(fds[0])), Stream(Pipe(fds[1]))))
+}
+
+/// Creates a connected pair of Unix domain stream sockets, as a full-duplex alternative to a
+/// pair of pipes. Data written to either end is read from the other.
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd"
+)))]
+pub fn socketpair() -> io::Result<(Stream, Stream)> {
+    let mut fds: [c_int; 2] = [-1, -1];
+    if unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) } != 0 {
+        return Err(oserr!());
+    }
+    debug_assert_ne!(fds[0], -1);
+    debug_assert_ne!(fds[1], -1);
+    // Wrap the fds immediately so that they're closed if setting their flags fails.
+    let (a, b) = (Stream(Pipe(fds[0])), Stream(Pipe(fds[1])));
+    for stream in [&a, &b] {
+        if unsafe { libc::fcntl(stream.as_raw_fd(), libc::F_SETFD, libc::FD_CLOEXEC) } == -1 {
+            return Err(oserr!());
+        }
+        stream.0.set_status_flag(libc::O_NONBLOCK, true)?;
+    }
+    Ok((a, b))
+}
+
+impl Stream {
+    /// The id of the process that created the other end, as recorded by the kernel when the
+    /// pair was created (`SO_PEERCRED`). For a pair created by [`socketpair`], this is the
+    /// creating process, even if the other end has since been passed to another process.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn peer_pid(&self) -> io::Result<u32> {
+        use std::{convert::TryFrom, mem};
+
+        let mut cred = libc::ucred {
+            pid: 0,
+            uid: 0,
+            gid: 0,
+        };
+        // A ucred is three 32-bit integers, so its size always fits.
+        let mut len = libc::socklen_t::try_from(mem::size_of::<libc::ucred>()).unwrap_or(0);
+        let res = unsafe {
+            libc::getsockopt(
+                self.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_PEERCRED,
+                std::ptr::addr_of_mut!(cred).cast(),
+                std::ptr::addr_of_mut!(len),
+            )
+        };
+        if res == -1 {
+            return Err(oserr!());
+        }
+        u32::try_from(cred.pid).map_err(|_| io::ErrorKind::InvalidData.into())
+    }
+}
+
+impl Read for Stream {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Write for Stream {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Pollable for Stream {}
+
+impl FromRawFd for Stream {
+    #[inline]
+    unsafe fn from_raw_fd(fd: RawFd) -> Self {
+        Stream(Pipe(fd))
+    }
+}
+
+impl AsRawFd for Stream {
+    #[inline]
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.0
+    }
+}
+
+impl IntoRawFd for Stream {
+    #[inline]
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl AsFd for Stream {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the file descriptor stays open for as long as `self` is borrowed.
+        unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+    }
+}
+
+impl From<Stream> for OwnedFd {
+    #[inline]
+    fn from(stream: Stream) -> OwnedFd {
+        unsafe { OwnedFd::from_raw_fd(stream.into_raw_fd()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Event, Poll, Timeout, Token};
+
+    #[test]
+    fn test_socketpair() {
+        let (mut a, mut b) = socketpair().unwrap();
+        for stream in [&a, &b] {
+            let flags = unsafe { libc::fcntl(stream.as_raw_fd(), libc::F_GETFD) };
+            assert_ne!(flags & libc::FD_CLOEXEC, 0);
+        }
+        let mut poll = Poll::new();
+        poll.register(&a, Token(0), Event::READ_WRITE);
+        poll.register(&b, Token(1), Event::READ_WRITE);
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 2);
+        assert!(poll.events().all(|(_, ev)| !ev.is_readable()));
+        let err = a.read(&mut [0; 16]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+        a.write_all(b"Hello").unwrap();
+        b.write_all(b"world").unwrap();
+        assert_eq!(poll.poll(Timeout::instant()).unwrap(), 2);
+        let readable: Vec<_> = poll
+            .events()
+            .filter(|(_, ev)| ev.is_readable())
+            .map(|(tok, _)| tok)
+            .collect();
+        assert!(readable.contains(&Token(0)) && readable.contains(&Token(1)));
+        let mut buf = [0; 5];
+        b.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        a.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"world");
+        drop(b);
+        assert_eq!(a.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_peer_pid() {
+        let (a, b) = socketpair().unwrap();
+        assert_eq!(a.peer_pid().unwrap(), std::process::id());
+        assert_eq!(b.peer_pid().unwrap(), std::process::id());
+    }
+}
@@ -0,0 +1,83 @@
+use crate::Writer;
+use std::io::{self, prelude::*};
+
+/// A [`Writer`] adaptor that keeps an in-memory copy of everything written to the pipe. Created
+/// by [`Writer::tee`].
+///
+/// Only the bytes actually accepted by the pipe are captured, so after a short write or a
+/// [`WouldBlock`](io::ErrorKind::WouldBlock) error, [`TeeWriter::captured`] still matches exactly
+/// what the reader will receive.
+#[derive(Debug)]
+pub struct TeeWriter {
+    writer: Writer,
+    captured: Vec<u8>,
+}
+
+impl TeeWriter {
+    pub(crate) fn new(writer: Writer) -> TeeWriter {
+        TeeWriter {
+            writer,
+            captured: Vec::new(),
+        }
+    }
+
+    /// All bytes written to the pipe so far.
+    #[inline]
+    #[must_use]
+    pub fn captured(&self) -> &[u8] {
+        &self.captured
+    }
+
+    /// Returns the underlying [`Writer`] and the captured bytes.
+    #[inline]
+    #[must_use]
+    pub fn into_parts(self) -> (Writer, Vec<u8>) {
+        (self.writer, self.captured)
+    }
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.captured.extend_from_slice(&buf[..written]);
+        Ok(written)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captured() {
+        let (mut reader, writer) = crate::new().unwrap();
+        let mut writer = writer.tee();
+        for chunk in [&b"Hello"[..], b", ", b"world"] {
+            writer.write_all(chunk).unwrap();
+        }
+        let (_, captured) = writer.into_parts();
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(captured, b"Hello, world");
+        assert_eq!(captured, buf);
+    }
+
+    #[test]
+    fn test_captured_short_write() {
+        let (mut reader, writer) = crate::new().unwrap();
+        let mut writer = writer.tee();
+        let msg = vec![1; 1024 * 1024];
+        let written = writer.write(&msg).unwrap();
+        assert!(written < msg.len());
+        assert_eq!(writer.captured().len(), written);
+        let (_, captured) = writer.into_parts();
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(captured, buf);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{poll::poll_fd, Event, Reader};
+use libc::c_int;
+use std::{
+    io::{self, prelude::*},
+    os::unix::prelude::AsRawFd,
+};
+
+/// Drains `reader` and asserts that the bytes read equal `expected`. Reading continues until the
+/// writer closes or no new data arrives for 100ms, so data that is still in flight from another
+/// thread is not missed.
+///
+/// Only available with the `test_support` feature.
+///
+/// # Panics
+/// Panics if the bytes read differ from `expected`, with a message showing the first differing
+/// byte and both byte strings, or if reading from the pipe fails.
+pub fn assert_reads(reader: &mut Reader, expected: &[u8]) {
+    const IDLE_TIMEOUT_MS: c_int = 100;
+    let mut actual = Vec::new();
+    let mut buf = [0; 4096];
+    loop {
+        // Bytes buffered by the reader itself are not reported by poll.
+        if !reader.has_pending() {
+            let ev = poll_fd(reader.as_raw_fd(), Event::all_readable(), IDLE_TIMEOUT_MS)
+                .expect("failed to poll reader");
+            if ev.is_empty() {
+                break;
+            }
+        }
+        match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => actual.extend_from_slice(&buf[..n]),
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock
+                    || e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => panic!("failed to read from pipe: {}", e),
+        }
+    }
+    if actual != expected {
+        let first_diff = actual
+            .iter()
+            .zip(expected)
+            .position(|(a, e)| a != e)
+            .unwrap_or_else(|| actual.len().min(expected.len()));
+        panic!(
+            "pipe contents differ at byte {}\n  expected ({} bytes): {:?}\n    actual ({} bytes): {:?}",
+            first_diff,
+            expected.len(),
+            String::from_utf8_lossy(expected),
+            actual.len(),
+            String::from_utf8_lossy(&actual),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_assert_reads() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello, world").unwrap();
+        assert_reads(&mut reader, b"Hello, world");
+    }
+
+    #[test]
+    fn test_assert_reads_pending() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"line\nHello, world").unwrap();
+        let mut line = Vec::new();
+        assert_eq!(reader.read_until(b'\n', &mut line).unwrap(), Some(5));
+        assert_reads(&mut reader, b"Hello, world");
+    }
+
+    #[test]
+    #[should_panic(expected = "pipe contents differ at byte 7")]
+    fn test_assert_reads_mismatch() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello, world").unwrap();
+        assert_reads(&mut reader, b"Hello, there");
+    }
+}
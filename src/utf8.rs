@@ -0,0 +1,120 @@
+use crate::Reader;
+use std::{io, str};
+
+/// A [`Reader`] adaptor for reading UTF-8 text. Created by [`Reader::utf8`].
+///
+/// A read from the pipe can end partway through a multibyte character. Rather than failing to
+/// decode the fragment, `Utf8Reader` holds it back until the rest of the character arrives.
+#[derive(Debug)]
+pub struct Utf8Reader {
+    reader: Reader,
+    // The bytes of an incomplete character at the end of the last read. At most 3 bytes long.
+    partial: Vec<u8>,
+}
+
+impl Utf8Reader {
+    pub(crate) fn new(reader: Reader) -> Utf8Reader {
+        Utf8Reader {
+            reader,
+            partial: Vec::new(),
+        }
+    }
+
+    /// Reads from the pipe and appends the complete characters read to `out`, returning the
+    /// number of bytes appended. `Ok(0)` means EOF.
+    ///
+    /// Like [`Read::read`], this returns an error of kind
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock) if no data is available, including when only
+    /// part of a character has arrived. Returns an error of kind
+    /// [`InvalidData`](io::ErrorKind::InvalidData) if the data is not valid UTF-8, or if the
+    /// pipe closes partway through a character.
+    pub fn read_str(&mut self, out: &mut String) -> io::Result<usize> {
+        const CHUNK_SIZE: usize = 4096;
+        let mut buf = Vec::with_capacity(self.partial.len() + CHUNK_SIZE);
+        buf.append(&mut self.partial);
+        loop {
+            let res = self.reader.read_into_spare(&mut buf);
+            match res {
+                Ok(0) if buf.is_empty() => return Ok(0),
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "stream ended partway through a UTF-8 character",
+                    ));
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.partial = buf;
+                    return Err(e);
+                }
+            }
+            let valid = match str::from_utf8(&buf) {
+                Ok(s) => s.len(),
+                Err(e) if e.error_len().is_none() => e.valid_up_to(),
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+            if valid > 0 {
+                self.partial = buf.split_off(valid);
+                // SAFETY: the first `valid` bytes were validated above.
+                out.push_str(unsafe { str::from_utf8_unchecked(&buf) });
+                return Ok(valid);
+            }
+            buf.reserve(CHUNK_SIZE);
+        }
+    }
+
+    /// Returns the underlying [`Reader`]. Any incomplete character is discarded.
+    #[inline]
+    #[must_use]
+    pub fn into_inner(self) -> Reader {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::prelude::*;
+
+    #[test]
+    fn test_split_character() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.utf8();
+        let mut out = String::new();
+        let msg = "naïve €".as_bytes();
+        // Split the three-byte euro sign after its first byte.
+        let (first, second) = msg.split_at(msg.len() - 2);
+        writer.write_all(first).unwrap();
+        assert_eq!(reader.read_str(&mut out).unwrap(), first.len() - 1);
+        assert_eq!(out, "naïve ");
+        assert_eq!(
+            reader.read_str(&mut out).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+        writer.write_all(second).unwrap();
+        assert_eq!(reader.read_str(&mut out).unwrap(), 3);
+        assert_eq!(out, "naïve €");
+        drop(writer);
+        assert_eq!(reader.read_str(&mut out).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.utf8();
+        writer.write_all(b"ok\xff").unwrap();
+        let err = reader.read_str(&mut String::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_truncated_at_eof() {
+        let (reader, mut writer) = crate::new().unwrap();
+        let mut reader = reader.utf8();
+        writer.write_all(&"€".as_bytes()[..2]).unwrap();
+        drop(writer);
+        let err = reader.read_str(&mut String::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
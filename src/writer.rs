@@ -1,55 +1,831 @@
-use crate::{pipe::Pipe, Pollable};
+use crate::{
+    fifo, framed::FramedWriter, pipe::Pipe, poll::poll_fd, CoalescingWriter, Event, Pollable,
+    TeeWriter, Timeout,
+};
 use libc::c_int;
 use std::{
-    io::{self, prelude::*},
+    convert::TryFrom,
+    io::{self, IoSlice, prelude::*},
     os::unix::{
-        io::{AsRawFd, FromRawFd, RawFd},
+        io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
         prelude::IntoRawFd,
     },
+    path::PathBuf,
+    process::Stdio,
+    thread,
+    time::{Duration, Instant},
 };
 
 /// The write end of a Unix pipe. Like [`Reader`](crate::Reader), Writer is non-blocking, and the
 /// [`CLOEXEC`](libc::FD_CLOEXEC) flag is set.
 #[derive(Debug)]
-pub struct Writer(Pipe);
+pub struct Writer {
+    pipe: Pipe,
+    // The path of the FIFO this writer was opened from, if any.
+    path: Option<PathBuf>,
+    blocked_count: u64,
+    bytes_written: u64,
+    sealed: bool,
+}
 
 impl Write for Writer {
     #[inline]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.0.write(buf)
+        self.check_sealed()?;
+        let res = self.pipe.write(buf);
+        self.count_written(res)
+    }
+
+    #[inline]
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.check_sealed()?;
+        let res = self.pipe.write_vectored(bufs);
+        self.count_written(res)
+    }
+
+    /// Writes all of `buf`, retrying if interrupted by a signal. Because the pipe is
+    /// non-blocking, this returns an error of kind [`WouldBlock`](io::ErrorKind::WouldBlock) as
+    /// soon as the pipe is full rather than waiting for the reader; part of `buf` may have been
+    /// written by then. To wait until everything has been written, use
+    /// [`Writer::write_all_blocking`].
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.check_sealed()?;
+        self.write_all_with(buf, |written| {
+            Err(io::Error::new(
+                io::ErrorKind::WouldBlock,
+                format!("pipe full after writing {} of {} bytes", written, buf.len()),
+            ))
+        })?;
+        Ok(())
     }
 
     #[inline]
     fn flush(&mut self) -> io::Result<()> {
-        self.0.flush()
+        self.pipe.flush()
     }
 }
 
 impl FromRawFd for Writer {
     #[inline]
     unsafe fn from_raw_fd(fd: RawFd) -> Self {
-        Writer(Pipe(fd))
+        Writer::new(fd)
     }
 }
 
 impl AsRawFd for Writer {
     #[inline]
     fn as_raw_fd(&self) -> RawFd {
-        self.0.as_raw_fd()
+        self.pipe.as_raw_fd()
     }
 }
 
 impl IntoRawFd for Writer {
     #[inline]
     fn into_raw_fd(self) -> RawFd {
-        self.0.into_raw_fd()
+        self.pipe.into_raw_fd()
+    }
+}
+
+impl AsFd for Writer {
+    #[inline]
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the file descriptor stays open for as long as `self` is borrowed.
+        unsafe { BorrowedFd::borrow_raw(self.as_raw_fd()) }
+    }
+}
+
+impl From<Writer> for OwnedFd {
+    #[inline]
+    fn from(writer: Writer) -> Self {
+        // SAFETY: `into_raw_fd` relinquishes ownership, so the file descriptor is closed once.
+        unsafe { OwnedFd::from_raw_fd(writer.into_raw_fd()) }
+    }
+}
+
+/// Takes ownership of the file descriptor. Its flags are left unchanged; use
+/// [`Writer::set_nonblocking`] to put it into non-blocking mode if needed.
+impl From<OwnedFd> for Writer {
+    #[inline]
+    fn from(fd: OwnedFd) -> Self {
+        Writer::new(fd.into_raw_fd())
     }
 }
 
 impl Pollable for Writer {}
 
+/// Transfers ownership of the file descriptor to a [`Stdio`], e.g. to connect it to a child
+/// process's standard output. The file descriptor is left non-blocking, which most programs do
+/// not expect of their standard streams; call [`Writer::set_nonblocking`] first to avoid this.
+impl From<Writer> for Stdio {
+    #[inline]
+    fn from(writer: Writer) -> Self {
+        unsafe { Stdio::from_raw_fd(writer.into_raw_fd()) }
+    }
+}
+
 impl Writer {
     pub(crate) fn new(n: c_int) -> Writer {
-        Writer(Pipe(n))
+        Writer {
+            pipe: Pipe(n),
+            path: None,
+            blocked_count: 0,
+            bytes_written: 0,
+            sealed: false,
+        }
+    }
+
+    pub(crate) fn with_path(n: c_int, path: PathBuf) -> Writer {
+        Writer {
+            pipe: Pipe(n),
+            path: Some(path),
+            blocked_count: 0,
+            bytes_written: 0,
+            sealed: false,
+        }
+    }
+
+    /// The number of writes that have failed with [`WouldBlock`](io::ErrorKind::WouldBlock)
+    /// because the pipe was full. A steadily increasing count indicates a slow reader.
+    #[inline]
+    #[must_use]
+    pub fn blocked_count(&self) -> u64 {
+        self.blocked_count
+    }
+
+    /// Resets [`Writer::blocked_count`] to zero.
+    #[inline]
+    pub fn reset_blocked_count(&mut self) {
+        self.blocked_count = 0;
+    }
+
+    /// The number of bytes written to the pipe by this handle.
+    #[inline]
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Resets [`Writer::bytes_written`] to zero.
+    #[inline]
+    pub fn reset_counters(&mut self) {
+        self.bytes_written = 0;
+    }
+
+    /// Marks the writer as sealed: from now on, writes fail immediately with an error of kind
+    /// [`NotConnected`](io::ErrorKind::NotConnected). Unlike dropping the writer, this leaves the
+    /// pipe open, so the reader does not see EOF and can finish draining data already written.
+    #[inline]
+    pub fn seal(&mut self) {
+        self.sealed = true;
+    }
+
+    /// Whether [`Writer::seal`] has been called.
+    #[inline]
+    #[must_use]
+    pub fn is_sealed(&self) -> bool {
+        self.sealed
+    }
+
+    #[inline]
+    pub(crate) fn check_sealed(&self) -> io::Result<()> {
+        if self.sealed {
+            Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "writer has been sealed",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    pub(crate) fn count_written(&mut self, res: io::Result<usize>) -> io::Result<usize> {
+        match &res {
+            Ok(n) => self.bytes_written += *n as u64,
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => self.blocked_count += 1,
+            Err(_) => {}
+        }
+        res
+    }
+
+    /// Closes the write end of the pipe, like dropping the writer, but reports any error from
+    /// `close` instead of ignoring it. The file descriptor is released either way. Once every
+    /// handle to the write end has been closed, the reader sees EOF.
+    #[inline]
+    pub fn close(self) -> io::Result<()> {
+        self.pipe.close()
+    }
+
+    /// Creates a new, independent handle to the same pipe, like
+    /// [`File::try_clone`](std::fs::File::try_clone). The file descriptor is duplicated with the
+    /// [`FD_CLOEXEC`](libc::FD_CLOEXEC) flag set, so dropping either handle leaves the other
+    /// usable, and the reader only sees EOF once both have been dropped. The clone is not
+    /// [sealed](Writer::seal), and its [`blocked_count`](Writer::blocked_count) and
+    /// [`bytes_written`](Writer::bytes_written) start at zero.
+    pub fn try_clone(&self) -> io::Result<Writer> {
+        let mut clone = self.dup()?;
+        clone.path.clone_from(&self.path);
+        Ok(clone)
+    }
+
+    /// Sets or clears the [`O_NONBLOCK`](libc::O_NONBLOCK) flag, leaving all other status flags
+    /// untouched. Pipes created by this crate start out non-blocking. Note that the flag belongs
+    /// to the open file description, so it is shared with any [duplicates](Pollable::dup).
+    #[inline]
+    pub fn set_nonblocking(&self, nonblocking: bool) -> io::Result<()> {
+        self.pipe.set_status_flag(libc::O_NONBLOCK, nonblocking)
+    }
+
+    /// Whether the [`O_NONBLOCK`](libc::O_NONBLOCK) flag is set.
+    #[inline]
+    pub fn is_nonblocking(&self) -> io::Result<bool> {
+        Ok(self.pipe.status_flags()? & libc::O_NONBLOCK != 0)
+    }
+
+    /// The capacity of the pipe's kernel buffer in bytes, as reported by `fcntl(F_GETPIPE_SZ)`.
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn pipe_size(&self) -> io::Result<usize> {
+        self.pipe.capacity()
+    }
+
+    /// Resizes the pipe's kernel buffer with `fcntl(F_SETPIPE_SZ)`, returning the new capacity.
+    /// The kernel rounds `bytes` up to a power-of-two number of pages. Unprivileged processes
+    /// cannot exceed `/proc/sys/fs/pipe-max-size`, and shrinking the buffer below the amount of
+    /// data currently queued fails with `EBUSY`.
+    #[cfg(target_os = "linux")]
+    #[inline]
+    pub fn set_pipe_size(&self, bytes: usize) -> io::Result<usize> {
+        self.pipe.set_capacity(bytes)
+    }
+
+    /// Returns the error pending on the file descriptor after a [`POLLERR`](Event::POLLERR) event,
+    /// or `None` if there is none.
+    ///
+    /// A zero-byte write is performed first, which reports and resets any pending error on file
+    /// descriptors that keep one. A pipe does not, and Linux doesn't even check for a reader on a
+    /// zero-byte write, so the state is also checked with `poll`: `POLLERR` on the write end of a
+    /// pipe means that every read end has been closed, which is reported as an error of kind
+    /// [`BrokenPipe`](io::ErrorKind::BrokenPipe). That condition persists for as long as no
+    /// reader is connected; for a FIFO, use [`Writer::reconnect`] to wait for a new one.
+    pub fn clear_error(&mut self) -> io::Result<Option<io::Error>> {
+        match self.pipe.write(&[]) {
+            Ok(_) => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+            Err(e) => return Ok(Some(e)),
+        }
+        let revents = poll_fd(self.as_raw_fd(), Event::empty(), 0)?;
+        Ok(if revents.contains(Event::POLLNVAL) {
+            Some(io::Error::from_raw_os_error(libc::EBADF))
+        } else if revents.contains(Event::POLLERR) {
+            Some(io::Error::from_raw_os_error(libc::EPIPE))
+        } else {
+            None
+        })
+    }
+
+    /// Re-opens the FIFO this writer was [opened](crate::fifo::open_writer) from, e.g. after its
+    /// reader has disconnected and another one has connected. If no reader has connected yet,
+    /// the open is retried until `timeout` elapses, after which an error of kind
+    /// [`TimedOut`](io::ErrorKind::TimedOut) is returned. The new file descriptor replaces the old
+    /// one under the same number, so existing [`Poll`](crate::Poll) registrations remain valid.
+    ///
+    /// Returns an error of kind [`InvalidInput`](io::ErrorKind::InvalidInput) if the writer was
+    /// not opened from a FIFO.
+    pub fn reconnect(&mut self, timeout: Timeout) -> io::Result<()> {
+        const RETRY_INTERVAL: Duration = Duration::from_millis(10);
+        let path = self.path.as_ref().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "writer is not bound to a FIFO")
+        })?;
+        let deadline = timeout.deadline();
+        let new_fd = loop {
+            match fifo::open_fd(path, libc::O_WRONLY) {
+                Ok(fd) => break fd,
+                Err(e) if e.raw_os_error() == Some(libc::ENXIO) => {}
+                Err(e) => return Err(e),
+            }
+            let now = Instant::now();
+            match deadline {
+                Some(deadline) if now >= deadline => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "timed out waiting for a reader to open the FIFO",
+                    ));
+                }
+                Some(deadline) => thread::sleep(RETRY_INTERVAL.min(deadline - now)),
+                None => thread::sleep(RETRY_INTERVAL),
+            }
+        };
+        let new_fd = Pipe(new_fd);
+        unsafe {
+            // dup2 clears FD_CLOEXEC on the target, so it has to be set again.
+            if libc::dup2(new_fd.0, self.pipe.0) == -1
+                || libc::fcntl(self.pipe.0, libc::F_SETFD, libc::FD_CLOEXEC) == -1
+            {
+                return Err(oserr!());
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes `buf` in chunks of at most [`PIPE_BUF`](libc::PIPE_BUF) bytes, stopping as soon
+    /// as the pipe is full, and returns the number of bytes written. If the pipe is full before
+    /// anything has been written, an error of kind [`WouldBlock`](io::ErrorKind::WouldBlock) is
+    /// returned instead.
+    ///
+    /// POSIX guarantees that a write of at most `PIPE_BUF` bytes to a pipe is atomic: it is
+    /// either written in full, or, for a non-blocking pipe, not at all, and it is never
+    /// interleaved with data from other writers. Each chunk is therefore written contiguously
+    /// even when several processes or threads share the pipe, though chunks from different
+    /// writers may be interleaved with one another. The returned count is always a whole number
+    /// of chunks, except possibly for a final chunk shorter than `PIPE_BUF`, so a caller can
+    /// resume with `&buf[written..]` without tearing a chunk.
+    ///
+    /// If the file descriptor is not actually a pipe, e.g. a socket, the guarantee does not hold
+    /// and a chunk may be written only in part. This returns as soon as that happens, so a
+    /// partial count that does not end on a chunk boundary always means a torn chunk; the rest of
+    /// `buf` is left for the caller to resume from.
+    pub fn write_chunked_atomic(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+        for chunk in buf.chunks(libc::PIPE_BUF) {
+            loop {
+                match self.write(chunk) {
+                    Ok(n) if n < chunk.len() => return Ok(written + n),
+                    Ok(n) => {
+                        written += n;
+                        break;
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock && written > 0 => {
+                        return Ok(written);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(written)
+    }
+
+    /// Writes `buf` like [`write`](Write::write), and also reports whether the pipe was empty,
+    /// as reported by `ioctl(FIONREAD)`, immediately before the write. A producer can use this
+    /// to signal a waiting consumer only when the pipe goes from empty to non-empty.
+    ///
+    /// The check and the write are separate system calls, so a reader draining the pipe in
+    /// between can make the pipe empty after the check; consumers should therefore drain until
+    /// [`WouldBlock`](io::ErrorKind::WouldBlock) before waiting for the next signal.
+    ///
+    /// Only available on Linux and Android, where `FIONREAD` reports the queued bytes on the
+    /// write end of a pipe as well as the read end.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn write_notifying(&mut self, buf: &[u8]) -> io::Result<(usize, bool)> {
+        let was_empty = self.pipe.bytes_queued()? == 0;
+        let written = self.write(buf)?;
+        Ok((written, was_empty))
+    }
+
+    /// Writes `buf` to the priority band of the file descriptor, for objects that support one
+    /// (see [`Event::is_priority_writable`]). Anonymous pipes and FIFOs have no priority band,
+    /// and none of the platforms this crate supports offer a band-aware write for them, so this
+    /// currently falls back to a normal [`write`](Write::write), and the data is delivered in
+    /// order with everything else.
+    #[inline]
+    pub fn write_priority(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write(buf)
+    }
+
+    /// Wraps the writer in a [`FramedWriter`], which sends length-delimited messages.
+    #[inline]
+    #[must_use]
+    pub fn framed(self) -> FramedWriter {
+        FramedWriter::new(self)
+    }
+
+    /// Wraps the writer in a [`CoalescingWriter`], which queues small writes in memory and sends
+    /// them to the pipe with a single syscall.
+    #[inline]
+    #[must_use]
+    pub fn coalesce(self) -> CoalescingWriter {
+        CoalescingWriter::new(self)
+    }
+
+    /// Wraps the writer in a [`TeeWriter`], which keeps an in-memory copy of everything written.
+    #[inline]
+    #[must_use]
+    pub fn tee(self) -> TeeWriter {
+        TeeWriter::new(self)
+    }
+
+    // Writes `buf` until done, retrying if interrupted by a signal and calling `on_would_block`
+    // with the number of bytes written so far whenever the pipe is full. `on_would_block` returns
+    // whether to retry; if it returns `false` or an error, writing stops early with that result.
+    fn write_all_with(
+        &mut self,
+        buf: &[u8],
+        mut on_would_block: impl FnMut(usize) -> io::Result<bool>,
+    ) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            match self.write(&buf[written..]) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::WriteZero,
+                        "failed to write whole buffer",
+                    ));
+                }
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    if !on_would_block(written)? {
+                        break;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(written)
+    }
+
+    /// Writes all of `buf`, waiting for the reader to make room whenever the pipe is full.
+    pub fn write_all_blocking(&mut self, buf: &[u8]) -> io::Result<()> {
+        let fd = self.as_raw_fd();
+        self.write_all_with(buf, |_| poll_fd(fd, Event::POLLOUT, -1).map(|_| true))?;
+        Ok(())
+    }
+
+    /// Writes as much of `buf` as possible within `timeout`, waiting for the pipe to become
+    /// writable whenever it is full. The deadline is fixed when the call starts, so it bounds the
+    /// call as a whole rather than each wait. Returns the number of bytes written, which is less
+    /// than `buf.len()` if the deadline elapsed first.
+    pub fn write_all_timeout(&mut self, buf: &[u8], timeout: Timeout) -> io::Result<usize> {
+        let (fd, deadline) = (self.as_raw_fd(), timeout.deadline());
+        self.write_all_with(buf, |_| {
+            let wait_ms = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Ok(false);
+                    }
+                    c_int::try_from(remaining.as_millis())
+                        .unwrap_or(c_int::MAX)
+                        .max(1)
+                }
+                None => -1,
+            };
+            poll_fd(fd, Event::POLLOUT, wait_ms).map(|_| true)
+        })
+    }
+
+    /// Writes all of `buf`, waiting for the pipe to become writable whenever it is full. Waits
+    /// start at 1ms and double after each consecutive [`WouldBlock`](io::ErrorKind::WouldBlock),
+    /// up to 100ms, so a slow reader is not met with a busy loop. Returns an error of kind
+    /// [`TimedOut`](io::ErrorKind::TimedOut) if `buf` has not been written in full after
+    /// `max_wait`; in that case, part of `buf` may already have been written.
+    pub fn write_with_backoff(&mut self, buf: &[u8], max_wait: Timeout) -> io::Result<usize> {
+        const MAX_BACKOFF_MS: c_int = 100;
+        let (fd, deadline) = (self.as_raw_fd(), max_wait.deadline());
+        let (mut backoff_ms, mut last_written) = (1, 0);
+        self.write_all_with(buf, |written| {
+            // Progress since the last wait means the reader is keeping up again.
+            if written != last_written {
+                backoff_ms = 1;
+                last_written = written;
+            }
+            let wait_ms = match deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            "timed out waiting for the pipe to become writable",
+                        ));
+                    }
+                    let remaining_ms = c_int::try_from(remaining.as_millis())
+                        .unwrap_or(c_int::MAX)
+                        .max(1);
+                    backoff_ms.min(remaining_ms)
+                }
+                None => backoff_ms,
+            };
+            poll_fd(fd, Event::POLLOUT, wait_ms)?;
+            backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
+            Ok(true)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_with_backoff() {
+        const LEN: usize = 256 * 1024;
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let handle = thread::spawn(move || {
+            let mut received = Vec::with_capacity(LEN);
+            let mut buf = [0; 4096];
+            while received.len() < LEN {
+                match reader.read(&mut buf) {
+                    Ok(n) => received.extend_from_slice(&buf[..n]),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            received
+        });
+        let msg: Vec<u8> = (0..=250).cycle().take(LEN).collect();
+        let res = writer.write_with_backoff(&msg, Timeout::secs(10).unwrap());
+        assert_ok!(res);
+        assert_eq!(res.unwrap(), LEN);
+        assert_eq!(handle.join().unwrap(), msg);
+    }
+
+    #[test]
+    fn test_write_all_blocking() {
+        const LEN: usize = 256 * 1024;
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            thread::sleep(Duration::from_millis(20));
+            while received.len() < LEN {
+                received.reserve(4096);
+                match reader.read_into_spare(&mut received) {
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    Err(e) => panic!("{}", e),
+                }
+            }
+            received
+        });
+        let msg: Vec<u8> = (0..=250).cycle().take(LEN).collect();
+        assert_ok!(writer.write_all_blocking(&msg));
+        assert_eq!(handle.join().unwrap(), msg);
+    }
+
+    #[test]
+    fn test_blocked_count() {
+        let (_reader, mut writer) = crate::new().unwrap();
+        let buf = vec![0; 1024 * 1024];
+        assert!(writer.write(&buf).unwrap() > 0);
+        assert_eq!(writer.blocked_count(), 0);
+        for _ in 0..3 {
+            assert_eq!(
+                writer.write(&buf).unwrap_err().kind(),
+                io::ErrorKind::WouldBlock
+            );
+        }
+        assert!(writer.write_all(&buf).is_err());
+        assert_eq!(writer.blocked_count(), 4);
+        writer.reset_blocked_count();
+        assert_eq!(writer.blocked_count(), 0);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn test_write_notifying() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        assert_eq!(writer.write_notifying(b"Hello").unwrap(), (5, true));
+        assert_eq!(writer.write_notifying(b"world").unwrap(), (5, false));
+        assert_eq!(reader.drain().unwrap(), 10);
+        assert_eq!(writer.write_notifying(b"again").unwrap(), (5, true));
+    }
+
+    #[test]
+    fn test_write_priority() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        let revents = poll_fd(writer.as_raw_fd(), Event::all_writable(), 0).unwrap();
+        assert!(revents.is_writable());
+        assert!(!revents.is_priority_writable());
+        writer.write_all(b"Hello").unwrap();
+        assert_eq!(writer.write_priority(b", world").unwrap(), 7);
+        let mut buf = [0; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 12);
+        assert_eq!(&buf[..12], b"Hello, world");
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_set_pipe_size() {
+        const SIZE: usize = 1024 * 1024;
+        let (_reader, writer) = crate::new().unwrap();
+        let size = writer.set_pipe_size(SIZE).unwrap();
+        assert!(size >= SIZE);
+        assert_eq!(writer.pipe_size().unwrap(), size);
+    }
+
+    #[test]
+    fn test_close() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        assert_ok!(writer.close());
+        let mut buf = [0; 5];
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"Hello");
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_try_clone() {
+        let (mut reader, writer) = crate::new().unwrap();
+        let mut clone = writer.try_clone().unwrap();
+        assert_ne!(clone.as_raw_fd(), writer.as_raw_fd());
+        drop(writer);
+        clone.write_all(b"Hello").unwrap();
+        drop(clone);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"Hello");
+    }
+
+    #[test]
+    fn test_write_chunked_atomic_short_write() {
+        let (stream, mut peer) = crate::socketpair().unwrap();
+        let size: c_int = 4096;
+        let res = unsafe {
+            libc::setsockopt(
+                stream.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_SNDBUF,
+                std::ptr::addr_of!(size).cast(),
+                libc::socklen_t::try_from(std::mem::size_of::<c_int>()).unwrap(),
+            )
+        };
+        assert_eq!(res, 0);
+        let mut writer = unsafe { Writer::from_raw_fd(stream.into_raw_fd()) };
+        let msg: Vec<u8> = (0..=250).cycle().take(64 * libc::PIPE_BUF).collect();
+        let (mut written, mut torn) = (0, false);
+        let (mut received, mut buf) = (Vec::new(), vec![0; 16 * 1024]);
+        while written < msg.len() {
+            match writer.write_chunked_atomic(&msg[written..]) {
+                Ok(n) => {
+                    torn |= n % libc::PIPE_BUF != 0 && written + n < msg.len();
+                    written += n;
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => panic!("{}", e),
+            }
+            match peer.read(&mut buf) {
+                Ok(n) => received.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                Err(e) => panic!("{}", e),
+            }
+        }
+        drop(writer);
+        peer.read_to_end(&mut received).unwrap();
+        assert!(torn);
+        assert_eq!(received, msg);
+    }
+
+    #[test]
+    fn test_write_chunked_atomic() {
+        const WRITERS: u8 = 4;
+        const CHUNKS: usize = 64;
+        let (mut reader, writer) = crate::new().unwrap();
+        let handles: Vec<_> = (0..WRITERS)
+            .map(|id| {
+                let mut writer = writer.try_clone().unwrap();
+                thread::spawn(move || {
+                    let msg = vec![id; libc::PIPE_BUF * CHUNKS];
+                    let mut written = 0;
+                    while written < msg.len() {
+                        match writer.write_chunked_atomic(&msg[written..]) {
+                            Ok(n) => written += n,
+                            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                                poll_fd(writer.as_raw_fd(), Event::POLLOUT, -1).unwrap();
+                            }
+                            Err(e) => panic!("{}", e),
+                        }
+                    }
+                })
+            })
+            .collect();
+        drop(writer);
+        let mut received = Vec::new();
+        loop {
+            received.reserve(64 * 1024);
+            match reader.read_into_spare(&mut received) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    poll_fd(reader.as_raw_fd(), Event::POLLIN, -1).unwrap();
+                }
+                Err(e) => panic!("{}", e),
+            }
+        }
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(
+            received.len(),
+            usize::from(WRITERS) * CHUNKS * libc::PIPE_BUF
+        );
+        for chunk in received.chunks(libc::PIPE_BUF) {
+            assert!(chunk.iter().all(|&b| b == chunk[0]), "chunk was torn");
+        }
+    }
+
+    #[test]
+    fn test_owned_fd() {
+        let (mut reader, writer) = crate::new().unwrap();
+        let fd = writer.as_raw_fd();
+        let mut writer = Writer::from(OwnedFd::from(writer));
+        assert_eq!(writer.as_fd().as_raw_fd(), fd);
+        writer.write_all(b"Hello").unwrap();
+        // Dropping the writer closes the fd exactly once, so the reader sees EOF.
+        drop(writer);
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"Hello");
+    }
+
+    #[test]
+    fn test_clear_error() {
+        let (reader, mut writer) = crate::new().unwrap();
+        assert!(writer.clear_error().unwrap().is_none());
+        drop(reader);
+        let revents = poll_fd(writer.as_raw_fd(), Event::POLLOUT, 0).unwrap();
+        assert!(revents.contains(Event::POLLERR));
+        let err = writer.clear_error().unwrap().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    }
+
+    #[test]
+    fn test_seal() {
+        let (mut reader, mut writer) = crate::new().unwrap();
+        writer.write_all(b"Hello").unwrap();
+        assert!(!writer.is_sealed());
+        writer.seal();
+        assert!(writer.is_sealed());
+        for res in [
+            writer.write(b", world").map(|_| ()),
+            writer.write_all(b", world"),
+        ] {
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotConnected);
+        }
+        let mut buf = [0; 16];
+        assert_eq!(reader.read(&mut buf).unwrap(), 5);
+        assert_eq!(&buf[..5], b"Hello");
+        assert_eq!(
+            reader.read(&mut buf).unwrap_err().kind(),
+            io::ErrorKind::WouldBlock
+        );
+    }
+
+    #[test]
+    fn test_write_all_timeout() {
+        const LEN: usize = 64 * 1024;
+        let builder = crate::PipeBuilder::new();
+        #[cfg(target_os = "linux")]
+        let builder = builder.capacity(4096);
+        let (mut reader, mut writer) = builder.build().unwrap();
+        let handle = thread::spawn(move || {
+            let mut received = Vec::with_capacity(LEN);
+            let mut buf = [0; 1024];
+            while received.len() < LEN {
+                match reader.read(&mut buf) {
+                    Ok(n) => received.extend_from_slice(&buf[..n]),
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => panic!("{}", e),
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            received
+        });
+        let msg: Vec<u8> = (0..=250).cycle().take(LEN).collect();
+        assert_eq!(
+            writer
+                .write_all_timeout(&msg, Timeout::secs(10).unwrap())
+                .unwrap(),
+            LEN
+        );
+        assert_eq!(handle.join().unwrap(), msg);
+    }
+
+    #[test]
+    fn test_write_all_timeout_elapsed() {
+        let (_reader, mut writer) = crate::new().unwrap();
+        let msg = vec![0; 1024 * 1024];
+        let start = Instant::now();
+        let written = writer
+            .write_all_timeout(&msg, Timeout::secs(1).unwrap())
+            .unwrap();
+        assert!(written > 0 && written < msg.len());
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_secs(1) && elapsed < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_write_with_backoff_timeout() {
+        let (_reader, mut writer) = crate::new().unwrap();
+        let msg = vec![0; 1024 * 1024];
+        let err = writer
+            .write_with_backoff(&msg, Timeout::instant())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
     }
 }